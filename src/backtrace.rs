@@ -0,0 +1,223 @@
+use gimli::{EndianSlice, RunTimeEndian};
+use object::{Object, ObjectSection, ObjectSymbol, SymbolKind};
+use regex::Regex;
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+/// Resolves the code addresses in ESP32/ESP8266 panic output (`Backtrace:`
+/// lines, register dumps and ESP8266 stack dumps) against the firmware ELF.
+pub struct Symbolizer {
+    /// `(start, end, name)` of every function, sorted by start address.
+    functions: Vec<(u64, u64, String)>,
+    /// DWARF line table rows sorted by address; `None` ends a sequence.
+    lines: Vec<(u64, Option<(usize, u64)>)>,
+    files: Vec<String>,
+    backtrace: Regex,
+    register: Regex,
+    stack: Regex,
+}
+
+impl Symbolizer {
+    pub fn load(path: &str) -> Result<Self, String> {
+        let data =
+            std::fs::read(path).map_err(|e| format!("Failed to read ELF '{}': {}", path, e))?;
+        let file =
+            object::File::parse(&*data).map_err(|e| format!("Invalid ELF '{}': {}", path, e))?;
+
+        let mut functions: Vec<(u64, u64, String)> = file
+            .symbols()
+            .filter(|s| s.kind() == SymbolKind::Text && s.size() > 0)
+            .filter_map(|s| {
+                let name = demangle(s.name().ok()?);
+                Some((s.address(), s.address() + s.size(), name))
+            })
+            .collect();
+        functions.sort_by_key(|(start, _, _)| *start);
+
+        let mut symbolizer = Self {
+            functions,
+            lines: Vec::new(),
+            files: Vec::new(),
+            backtrace: Regex::new(r"0x([0-9a-fA-F]{8}):0x[0-9a-fA-F]{8}").unwrap(),
+            register: Regex::new(r"(?i)\b(pc|a0|mepc|ra|epc[1-3])\s*[:=]\s*0x([0-9a-f]{8})")
+                .unwrap(),
+            stack: Regex::new(r"^[0-9a-f]{8}:((?:\s+[0-9a-f]{8}){4})").unwrap(),
+        };
+        // Without readable debug info only function names are shown
+        if symbolizer.load_lines(&file).is_err() {
+            symbolizer.lines.clear();
+            symbolizer.files.clear();
+        }
+        Ok(symbolizer)
+    }
+
+    fn load_lines(&mut self, file: &object::File) -> Result<(), gimli::Error> {
+        let endian = if file.is_little_endian() {
+            RunTimeEndian::Little
+        } else {
+            RunTimeEndian::Big
+        };
+        let sections = gimli::DwarfSections::load(|id| -> Result<Cow<[u8]>, gimli::Error> {
+            Ok(file
+                .section_by_name(id.name())
+                .and_then(|section| section.uncompressed_data().ok())
+                .unwrap_or(Cow::Borrowed(&[])))
+        })?;
+        let dwarf = sections.borrow(|section| EndianSlice::new(section, endian));
+        let cwd = std::env::current_dir().ok();
+        let mut file_ids: HashMap<String, usize> = HashMap::new();
+
+        let mut units = dwarf.units();
+        while let Some(header) = units.next()? {
+            let unit = dwarf.unit(header)?;
+            let Some(program) = unit.line_program.clone() else {
+                continue;
+            };
+            let comp_dir = unit
+                .comp_dir
+                .map(|dir| PathBuf::from(dir.to_string_lossy().into_owned()))
+                .unwrap_or_default();
+
+            let mut sequence = Vec::new();
+            let mut rows = program.rows();
+            while let Some((header, row)) = rows.next_row()? {
+                if row.end_sequence() {
+                    // Sequences at 0 belong to functions the linker discarded
+                    if sequence.first().is_some_and(|(address, _)| *address != 0) {
+                        self.lines.append(&mut sequence);
+                        self.lines.push((row.address(), None));
+                    }
+                    sequence.clear();
+                    continue;
+                }
+
+                let Some(entry) = row.file(header) else {
+                    continue;
+                };
+                let mut path = comp_dir.clone();
+                if let Some(dir) = entry.directory(header) {
+                    path.push(dwarf.attr_string(&unit, dir)?.to_string_lossy().as_ref());
+                }
+                path.push(
+                    dwarf
+                        .attr_string(&unit, entry.path_name())?
+                        .to_string_lossy()
+                        .as_ref(),
+                );
+                let path = crate::defmt_stream::project_relative(&path, cwd.as_deref())
+                    .display()
+                    .to_string();
+
+                let id = *file_ids.entry(path).or_insert_with_key(|path| {
+                    self.files.push(path.clone());
+                    self.files.len() - 1
+                });
+                let line = row.line().map(|l| l.get()).unwrap_or(0);
+                sequence.push((row.address(), Some((id, line))));
+            }
+        }
+
+        // A sequence may start where another ends; the start must win
+        self.lines
+            .sort_by_key(|(address, row)| (*address, row.is_some()));
+        Ok(())
+    }
+
+    /// `function at file:line` for a code address, if it is in the firmware.
+    fn resolve(&self, address: u64) -> Option<String> {
+        let index = self
+            .functions
+            .partition_point(|(start, _, _)| *start <= address);
+        let (_, end, name) = self.functions.get(index.checked_sub(1)?)?;
+        if address >= *end {
+            return None;
+        }
+
+        let index = self.lines.partition_point(|(start, _)| *start <= address);
+        match index.checked_sub(1).and_then(|i| self.lines[i].1) {
+            Some((file, line)) => Some(format!("{} at {}:{}", name, self.files[file], line)),
+            None => Some(name.clone()),
+        }
+    }
+
+    /// Like [`Self::resolve`], retrying Xtensa return addresses (e.g. `A0`)
+    /// whose top bits hold the call window size instead of the code region.
+    fn resolve_code(&self, address: u64) -> Option<String> {
+        self.resolve(address)
+            .or_else(|| self.resolve((address & 0x3fff_ffff) | 0x4000_0000))
+    }
+
+    /// Decoded lines for a line of panic output, empty for any other line.
+    pub fn decode_line(&self, line: &str) -> Vec<String> {
+        let mut addresses: Vec<(String, u64)> = Vec::new();
+
+        if line.contains("Backtrace:") {
+            for (frame, cap) in self.backtrace.captures_iter(line).enumerate() {
+                addresses.push((format!("#{}", frame), parse_hex(&cap[1])));
+            }
+        } else if let Some(cap) = self.stack.captures(line) {
+            for word in cap[1].split_whitespace() {
+                addresses.push(("stack".to_string(), parse_hex(word)));
+            }
+        } else {
+            for cap in self.register.captures_iter(line) {
+                addresses.push((cap[1].to_uppercase(), parse_hex(&cap[2])));
+            }
+        }
+
+        addresses
+            .into_iter()
+            .filter(|(_, address)| *address != 0)
+            .filter_map(|(label, address)| {
+                let location = self.resolve_code(address)?;
+                Some(format!("{} 0x{:08x}: {}", label, address, location))
+            })
+            .collect()
+    }
+}
+
+fn parse_hex(digits: &str) -> u64 {
+    u64::from_str_radix(digits, 16).unwrap_or(0)
+}
+
+/// Turns `_ZN` nested names (legacy Rust and simple C++ symbols) into
+/// `a::b::c`, dropping Rust's hash suffix and C++ parameter types.
+fn demangle(symbol: &str) -> String {
+    let Some(mut rest) = symbol.strip_prefix("_ZN") else {
+        return symbol.to_string();
+    };
+
+    let mut parts: Vec<String> = Vec::new();
+    loop {
+        let digits = rest.bytes().take_while(u8::is_ascii_digit).count();
+        let Ok(len) = rest[..digits].parse::<usize>() else {
+            break;
+        };
+        let Some(part) = rest.get(digits..digits + len) else {
+            return symbol.to_string();
+        };
+        parts.push(part.to_string());
+        rest = &rest[digits + len..];
+    }
+    if parts.is_empty() {
+        return symbol.to_string();
+    }
+
+    if parts.last().is_some_and(|p| {
+        p.len() == 17 && p.starts_with('h') && p[1..].bytes().all(|b| b.is_ascii_hexdigit())
+    }) {
+        parts.pop();
+    }
+    parts
+        .join("::")
+        .replace("$LT$", "<")
+        .replace("$GT$", ">")
+        .replace("$RF$", "&")
+        .replace("$BP$", "*")
+        .replace("$C$", ",")
+        .replace("$u20$", " ")
+        .replace("$u7b$", "{")
+        .replace("$u7d$", "}")
+        .replace("..", "::")
+}
@@ -1,102 +1,666 @@
 #![cfg(feature = "ble")]
 
-use btleplug::api::{Central, Manager as _, Peripheral as _, ScanFilter};
-use btleplug::platform::Manager;
+use btleplug::api::{
+    Central, CentralEvent, CharPropFlags, Characteristic, Manager as _, Peripheral as _,
+    PeripheralProperties, ScanFilter, ValueNotification, WriteType,
+};
+use btleplug::platform::{Adapter, Manager, Peripheral, PeripheralId};
 use futures::stream::StreamExt;
+use inline_colorization::*;
 use inquire::Select;
 use std::error::Error;
+use std::io::IsTerminal;
 use std::sync::mpsc;
 use std::time::Duration;
 use uuid::Uuid;
 
+use crate::framing::FrameStats;
+use crate::gatt::{BLUETOOTH_BASE_UUID, GattChannel};
+
 //const NUS_SERVICE_UUID: Uuid = Uuid::from_u128(0x6e400001_b5a3_f393_e0a9_e50e24dcca9e);
 const NUS_TX_CHAR_UUID: Uuid = Uuid::from_u128(0x6e400003_b5a3_f393_e0a9_e50e24dcca9e);
+const NUS_RX_CHAR_UUID: Uuid = Uuid::from_u128(0x6e400002_b5a3_f393_e0a9_e50e24dcca9e);
 
 #[derive(Debug, Clone)]
 pub enum BleEvent {
-    Payload(String),
+    /// Raw notification bytes, so binary encodings like defmt survive.
+    Payload(Vec<u8>),
+    /// A notification from one of the `--ble-char` characteristics.
+    Notification(Uuid, Vec<u8>),
     Disconnected,
+    /// Reconnecting: looking for the device again.
+    Scanning,
+    /// Reconnecting: found it, connecting and subscribing.
+    Connecting,
+    /// Streaming again, with the new RX writer if the device has one.
+    Connected(Option<NusWriter>),
 }
 
-/// Starts the BLE interactive menu and background streaming task.
-/// Returns the Tokio runtime so the caller can keep it alive for the duration of the app.
-pub fn start_ble_stream(
-    tx: mpsc::Sender<BleEvent>,
-) -> Result<tokio::runtime::Runtime, Box<dyn Error>> {
-    let rt = tokio::runtime::Builder::new_multi_thread()
-        .enable_all()
-        .build()?;
+/// Which device to connect to (`--ble-name`, `--ble-address`,
+/// `--ble-service`) and how long to scan for it (`--ble-scan`).
+#[derive(Clone)]
+pub struct BleOptions {
+    name: Option<String>,
+    address: Option<String>,
+    service: Option<Uuid>,
+    scan: Duration,
+    /// Characteristics to subscribe to instead of NUS.
+    chars: Vec<GattChannel>,
+}
 
-    // Block the main thread while the interactive setup happens first
-    rt.block_on(async {
-        let manager = Manager::new().await?;
-        let adapters = manager.adapters().await?;
-        let central = adapters.into_iter().next().ok_or("No Bluetooth Adapters found on this system")?;
+impl BleOptions {
+    pub fn from_config(config: &crate::config::MergedConfig) -> Result<Self, Box<dyn Error>> {
+        Ok(Self {
+            name: config.ble_name.clone(),
+            address: config.ble_address.clone(),
+            service: config.ble_service.as_deref().map(parse_uuid).transpose()?,
+            scan: Duration::from_secs(config.ble_scan_secs),
+            chars: config
+                .ble_chars
+                .iter()
+                .map(GattChannel::from_spec)
+                .collect::<Result<_, _>>()?,
+        })
+    }
+
+    pub fn service(&self) -> Option<Uuid> {
+        self.service
+    }
+
+    fn has_filters(&self) -> bool {
+        self.name.is_some() || self.address.is_some() || self.service.is_some()
+    }
+
+    pub fn matches(&self, id: &str, properties: &PeripheralProperties) -> bool {
+        let name_ok = self.name.as_ref().is_none_or(|wanted| {
+            properties
+                .local_name
+                .as_ref()
+                .is_some_and(|name| name.to_lowercase().contains(&wanted.to_lowercase()))
+        });
+        let address_ok = self.address.as_ref().is_none_or(|wanted| {
+            wanted.eq_ignore_ascii_case(id)
+                || wanted.eq_ignore_ascii_case(&properties.address.to_string())
+        });
+        let service_ok = self
+            .service
+            .is_none_or(|wanted| properties.services.contains(&wanted));
+        name_ok && address_ok && service_ok
+    }
+}
 
-        println!("Scanning for BLE devices for 4 seconds...");
-        central.start_scan(ScanFilter::default()).await?;
-        tokio::time::sleep(Duration::from_secs(4)).await;
+/// Parses a full UUID or a 16-bit assigned number such as `180d`.
+pub fn parse_uuid(text: &str) -> Result<Uuid, String> {
+    let short = text.trim_start_matches("0x");
+    if short.len() == 4
+        && let Ok(value) = u16::from_str_radix(short, 16)
+    {
+        return Ok(Uuid::from_u128(
+            ((value as u128) << 96) | BLUETOOTH_BASE_UUID,
+        ));
+    }
+    Uuid::parse_str(text).map_err(|e| format!("Invalid BLE UUID '{}': {}", text, e))
+}
+
+/// Scans for the device `options` describe. With filters the scan stops at the
+/// first poll that sees a match, taking the strongest RSSI if several showed up
+/// in it; otherwise the user picks from everything seen.
+/// Status lines go to stderr, as stdout only carries data in `--headless` runs.
+async fn find_peripheral(
+    central: &Adapter,
+    options: &BleOptions,
+) -> Result<(Peripheral, String), Box<dyn Error>> {
+    let filter = ScanFilter {
+        services: options.service.into_iter().collect(),
+    };
+    central.start_scan(filter).await?;
 
-        let peripherals = central.peripherals().await?;
-        if peripherals.is_empty() {
-            return Err("No BLE devices found in range".into());
+    if options.has_filters() {
+        eprintln!(
+            "Scanning up to {}s for a matching BLE device...",
+            options.scan.as_secs()
+        );
+        let deadline = tokio::time::Instant::now() + options.scan;
+        loop {
+            let mut best: Option<(i16, Peripheral, String)> = None;
+            for p in central.peripherals().await? {
+                let properties = p.properties().await?.unwrap_or_default();
+                let id = p.id().to_string();
+                if !options.matches(&id, &properties) {
+                    continue;
+                }
+                let rssi = properties.rssi.unwrap_or(i16::MIN);
+                if best
+                    .as_ref()
+                    .is_none_or(|(strongest, _, _)| rssi > *strongest)
+                {
+                    let name = properties
+                        .local_name
+                        .unwrap_or_else(|| "Unknown Device".to_string());
+                    best = Some((rssi, p, format!("[{}] {}", id, name)));
+                }
+            }
+            if let Some((_, peripheral, label)) = best {
+                central.stop_scan().await.ok();
+                return Ok((peripheral, label));
+            }
+            if tokio::time::Instant::now() >= deadline {
+                central.stop_scan().await.ok();
+                return Err(
+                    "No BLE device matching --ble-name/--ble-address/--ble-service found in range"
+                        .into(),
+                );
+            }
+            tokio::time::sleep(Duration::from_millis(250)).await;
         }
+    }
 
-        let mut device_map = Vec::new();
-        let mut display_list = Vec::new();
+    eprintln!(
+        "Scanning for BLE devices for {} seconds...",
+        options.scan.as_secs()
+    );
+    tokio::time::sleep(options.scan).await;
+    central.stop_scan().await.ok();
 
-        for p in peripherals {
-            let properties = p.properties().await?.unwrap_or_default();
-            let name = properties.local_name.unwrap_or_else(|| "Unknown Device".to_string());
-            let mac = p.id().to_string();
+    let peripherals = central.peripherals().await?;
+    if peripherals.is_empty() {
+        return Err("No BLE devices found in range".into());
+    }
+    if !std::io::stdin().is_terminal() {
+        return Err(
+            "Pick a BLE device with --ble-name or --ble-address when not running interactively"
+                .into(),
+        );
+    }
 
-            display_list.push(format!("[{}] {}", mac, name));
-            device_map.push(p);
+    let mut devices = Vec::new();
+    for p in peripherals {
+        let properties = p.properties().await?.unwrap_or_default();
+        let name = properties
+            .local_name
+            .unwrap_or_else(|| "Unknown Device".to_string());
+        devices.push((properties.rssi, p.id().to_string(), name, p));
+    }
+    // Strongest signal first; devices without an RSSI last
+    devices.sort_by_key(|(rssi, _, _, _)| std::cmp::Reverse(rssi.unwrap_or(i16::MIN)));
+
+    let mut device_map = Vec::new();
+    let mut display_list = Vec::new();
+    for (rssi, mac, name, p) in devices {
+        let rssi = rssi.map_or("   ?".to_string(), |r| format!("{:4}", r));
+        display_list.push(format!("{} dBm [{}] {}", rssi, mac, name));
+        device_map.push(p);
+    }
+
+    let selection = Select::new("Select Target BLE device: ", display_list.clone())
+        .with_page_size(10)
+        .prompt()?;
+
+    let index = display_list.iter().position(|r| r == &selection).unwrap();
+    Ok((device_map.remove(index), selection))
+}
+
+/// The NUS RX characteristic of the connected device, for sending input.
+#[derive(Debug, Clone)]
+pub struct NusWriter {
+    peripheral: Peripheral,
+    rx_char: Characteristic,
+    write_type: WriteType,
+}
+
+impl NusWriter {
+    fn new(peripheral: &Peripheral, rx_char: &Characteristic) -> Self {
+        let write_type = if rx_char
+            .properties
+            .contains(CharPropFlags::WRITE_WITHOUT_RESPONSE)
+        {
+            WriteType::WithoutResponse
+        } else {
+            WriteType::WithResponse
+        };
+        Self {
+            peripheral: peripheral.clone(),
+            rx_char: rx_char.clone(),
+            write_type,
         }
+    }
 
-        let selection = Select::new("Select Target BLE device: ", display_list.clone())
-            .with_page_size(10)
-            .prompt()?;
+    /// Writes `data` in packets that fit the negotiated MTU.
+    async fn write(&self, data: &[u8]) -> btleplug::Result<()> {
+        // Each ATT packet spends 3 bytes on its header
+        let chunk_size = (self.peripheral.mtu() as usize).saturating_sub(3).max(1);
+        for chunk in data.chunks(chunk_size) {
+            self.peripheral
+                .write(&self.rx_char, chunk, self.write_type)
+                .await?;
+        }
+        Ok(())
+    }
+}
 
-        let index = display_list.iter().position(|r| r == &selection).unwrap();
-        let peripheral = device_map.remove(index);
+type Notifications = std::pin::Pin<Box<dyn futures::Stream<Item = ValueNotification> + Send>>;
 
-        println!("Connecting to {}...", selection);
+/// Connects (if needed) and subscribes to the NUS TX characteristic, or to
+/// every `--ble-char` characteristic when some are given.
+async fn connect_nus(
+    peripheral: &Peripheral,
+    gatt: &[GattChannel],
+) -> Result<(Notifications, Option<NusWriter>), Box<dyn Error + Send + Sync>> {
+    if !peripheral.is_connected().await? {
         peripheral.connect().await?;
-        println!("Connected! Discovering services....");
-        peripheral.discover_services().await?;
+    }
+    peripheral.discover_services().await?;
+    let chars = peripheral.characteristics();
 
-        // Hunt down the NUS TX characteristic 
-        let chars = peripheral.characteristics();
+    if gatt.is_empty() {
+        // Hunt down the NUS TX characteristic
         let tx_char = chars
             .iter()
             .find(|c| c.uuid == NUS_TX_CHAR_UUID)
             .ok_or("Nordic UART Service (NUS) TX characteristic not found! Ensure your board is flashing the correct profile.")?;
-
-        println!("Subscribing to the NUS TX stream....");
         peripheral.subscribe(tx_char).await?;
+    } else {
+        for channel in gatt {
+            let characteristic =
+                chars
+                    .iter()
+                    .find(|c| c.uuid == channel.uuid)
+                    .ok_or_else(|| {
+                        format!(
+                            "Characteristic {} not found on the device (see --ble-list)",
+                            channel.uuid
+                        )
+                    })?;
+            if !characteristic
+                .properties
+                .intersects(CharPropFlags::NOTIFY | CharPropFlags::INDICATE)
+            {
+                return Err(format!(
+                    "Characteristic {} does not support notifications",
+                    channel.uuid
+                )
+                .into());
+            }
+            peripheral.subscribe(characteristic).await?;
+        }
+    }
 
-        let mut notification_stream = peripheral.notifications().await?;
+    // Receive-only devices simply leave input unsupported
+    let writer = chars
+        .iter()
+        .find(|c| c.uuid == NUS_RX_CHAR_UUID)
+        .map(|rx_char| NusWriter::new(peripheral, rx_char));
 
-        println!("Stream Established! Handing over to ComChan Monitor...\n");
+    let notifications = peripheral.notifications().await?;
+    Ok((notifications, writer))
+}
 
-        tokio::spawn(async move {
-            while let Some(data) = notification_stream.next().await {
-                if data.uuid == NUS_TX_CHAR_UUID {
-                    let text = String::from_utf8_lossy(&data.value).to_string();
+/// Scans until the peripheral with `id` advertises again, or the scan time
+/// ends. Backends keep a disconnected device cached, so only a fresh
+/// advertisement counts as seeing it.
+async fn rescan(
+    central: &Adapter,
+    id: &PeripheralId,
+    scan: Duration,
+) -> Result<Peripheral, Box<dyn Error + Send + Sync>> {
+    let mut events = central.events().await?;
+    central.start_scan(ScanFilter::default()).await?;
+    let advertised = async {
+        while let Some(event) = events.next().await {
+            match event {
+                CentralEvent::DeviceDiscovered(seen)
+                | CentralEvent::RssiUpdate { id: seen, .. }
+                | CentralEvent::ManufacturerDataAdvertisement { id: seen, .. }
+                | CentralEvent::ServiceDataAdvertisement { id: seen, .. }
+                | CentralEvent::ServicesAdvertisement { id: seen, .. }
+                    if seen == *id =>
+                {
+                    return true;
+                }
+                _ => {}
+            }
+        }
+        false
+    };
+    let found = tokio::time::timeout(scan, advertised)
+        .await
+        .unwrap_or(false);
+    central.stop_scan().await.ok();
+    if !found {
+        return Err("BLE device not found".into());
+    }
+    Ok(central.peripheral(id).await?)
+}
+
+/// Forwards notifications and, whenever the link drops, reconnects to the same
+/// peripheral with exponential backoff. Ends once the receiver is gone.
+async fn supervise(
+    central: Adapter,
+    mut peripheral: Peripheral,
+    mut notifications: Notifications,
+    options: BleOptions,
+    tx: mpsc::Sender<BleEvent>,
+) {
+    const MAX_BACKOFF: Duration = Duration::from_secs(30);
+    let id = peripheral.id();
 
-                    if tx.send(BleEvent::Payload(text)).is_err() {
+    loop {
+        loop {
+            match tokio::time::timeout(Duration::from_secs(1), notifications.next()).await {
+                Ok(Some(data)) => {
+                    let event = if options.chars.is_empty() {
+                        if data.uuid != NUS_TX_CHAR_UUID {
+                            continue;
+                        }
+                        BleEvent::Payload(data.value)
+                    } else {
+                        BleEvent::Notification(data.uuid, data.value)
+                    };
+                    if tx.send(event).is_err() {
+                        return;
+                    }
+                }
+                Ok(None) => break,
+                // Not every backend ends the stream when the link drops
+                Err(_) => {
+                    if !peripheral.is_connected().await.unwrap_or(false) {
                         break;
                     }
                 }
             }
+        }
+        if tx.send(BleEvent::Disconnected).is_err() {
+            return;
+        }
 
-            eprintln!("BLE notification stream disconnected unexpectedly.");
-            let _ = tx.send(BleEvent::Disconnected);
-        });
+        let mut backoff = Duration::from_secs(1);
+        notifications = loop {
+            if tx.send(BleEvent::Scanning).is_err() {
+                return;
+            }
+            if let Ok(found) = rescan(&central, &id, options.scan).await {
+                peripheral = found;
+                if tx.send(BleEvent::Connecting).is_err() {
+                    return;
+                }
+                if let Ok((notifications, writer)) = connect_nus(&peripheral, &options.chars).await
+                {
+                    if tx.send(BleEvent::Connected(writer)).is_err() {
+                        return;
+                    }
+                    break notifications;
+                }
+                peripheral.disconnect().await.ok();
+            }
+            tokio::time::sleep(backoff).await;
+            backoff = (backoff * 2).min(MAX_BACKOFF);
+        };
+    }
+}
 
-        Ok::<(), Box<dyn Error>>(())
+/// Finds the device (see [`find_peripheral`]), connects and hands the link to
+/// a background task that streams notifications and reconnects after link loss.
+/// Returns the Tokio runtime so the caller can keep it alive for the duration of the app,
+/// the writer for the NUS RX characteristic if the device has one, and the device label.
+pub fn start_ble_stream(
+    tx: mpsc::Sender<BleEvent>,
+    options: &BleOptions,
+) -> Result<(tokio::runtime::Runtime, Option<NusWriter>, String), Box<dyn Error>> {
+    let rt = tokio::runtime::Builder::new_multi_thread()
+        .enable_all()
+        .build()?;
+
+    // Block the main thread while the device is found and set up
+    let (writer, label) = rt.block_on(async {
+        let central = first_adapter().await?;
+        let (peripheral, selection) = find_peripheral(&central, options).await?;
+
+        eprintln!("Connecting to {}...", selection);
+        let (notifications, writer) = connect_nus(&peripheral, &options.chars)
+            .await
+            .map_err(|e| e as Box<dyn Error>)?;
+
+        eprintln!("Stream Established! Handing over to ComChan Monitor...\n");
+
+        tokio::spawn(supervise(
+            central,
+            peripheral,
+            notifications,
+            options.clone(),
+            tx,
+        ));
+
+        Ok::<_, Box<dyn Error>>((writer, selection))
     })?;
 
-    Ok(rt)
+    Ok((rt, writer, label))
+}
+
+pub async fn first_adapter() -> Result<Adapter, Box<dyn Error>> {
+    let manager = Manager::new().await?;
+    let adapters = manager.adapters().await?;
+    Ok(adapters
+        .into_iter()
+        .next()
+        .ok_or("No Bluetooth Adapters found on this system")?)
+}
+
+/// `--ble-list`: connects to the device and prints its services and
+/// characteristics, so the UUIDs for `--ble-char` can be picked.
+pub fn list_gatt(config: &crate::config::MergedConfig) -> Result<(), Box<dyn Error>> {
+    let options = BleOptions::from_config(config)?;
+    let rt = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()?;
+
+    rt.block_on(async {
+        let central = first_adapter().await?;
+        let (peripheral, selection) = find_peripheral(&central, &options).await?;
+        eprintln!("Connecting to {}...", selection);
+        if !peripheral.is_connected().await? {
+            peripheral.connect().await?;
+        }
+        peripheral.discover_services().await?;
+
+        println!("\n{style_bold}GATT services of {}{style_reset}", selection);
+        for service in peripheral.services() {
+            println!("\n{color_cyan}Service {}{color_reset}", service.uuid);
+            for characteristic in &service.characteristics {
+                println!(
+                    "  {} [{}]{}",
+                    characteristic.uuid,
+                    property_names(characteristic.properties).join(", "),
+                    known_name(&characteristic.uuid)
+                        .map(|name| format!(" {color_green}{}{color_reset}", name))
+                        .unwrap_or_default()
+                );
+            }
+        }
+
+        peripheral.disconnect().await?;
+        Ok::<_, Box<dyn Error>>(())
+    })
+}
+
+fn property_names(properties: CharPropFlags) -> Vec<&'static str> {
+    [
+        (CharPropFlags::READ, "read"),
+        (CharPropFlags::WRITE, "write"),
+        (
+            CharPropFlags::WRITE_WITHOUT_RESPONSE,
+            "write-without-response",
+        ),
+        (CharPropFlags::NOTIFY, "notify"),
+        (CharPropFlags::INDICATE, "indicate"),
+    ]
+    .into_iter()
+    .filter(|(flag, _)| properties.contains(*flag))
+    .map(|(_, name)| name)
+    .collect()
+}
+
+fn known_name(uuid: &Uuid) -> Option<&'static str> {
+    match *uuid {
+        NUS_TX_CHAR_UUID => Some("NUS TX"),
+        NUS_RX_CHAR_UUID => Some("NUS RX"),
+        _ => crate::gatt::sig_name(uuid),
+    }
+}
+
+/// [`DataSource`](crate::source::DataSource) adapter over the NUS notification stream,
+/// or over the `--ble-char` characteristics decoded into lines.
+/// Owns the Tokio runtime so the background task lives as long as the source.
+pub struct BleSource {
+    rt: tokio::runtime::Runtime,
+    rx: mpsc::Receiver<BleEvent>,
+    writer: Option<NusWriter>,
+    label: String,
+    connected: bool,
+    is_open: bool,
+    /// What the reconnect task is doing while the link is down.
+    status: &'static str,
+    /// Notifications that arrived before the source was reopened.
+    pending: Vec<u8>,
+    /// Subscribed `--ble-char` characteristics; empty in NUS mode.
+    channels: Vec<GattChannel>,
+    /// Decoded characteristic notifications not yet handed over.
+    lines: Vec<String>,
+    stats: FrameStats,
+}
+
+impl BleSource {
+    /// Finds the device, falling back to the interactive picker, and starts streaming.
+    pub fn start(config: &crate::config::MergedConfig) -> Result<Self, Box<dyn Error>> {
+        let options = BleOptions::from_config(config)?;
+        let (tx, rx) = mpsc::channel();
+        let (rt, writer, label) = start_ble_stream(tx, &options)?;
+
+        Ok(Self {
+            rt,
+            rx,
+            writer,
+            label,
+            connected: true,
+            is_open: false,
+            status: "BLE Connection Lost",
+            pending: Vec::new(),
+            channels: options.chars,
+            lines: Vec::new(),
+            stats: FrameStats {
+                framing: "GATT",
+                ..FrameStats::default()
+            },
+        })
+    }
+
+    /// Applies up to `limit` events from the BLE task.
+    fn poll_events(&mut self, limit: usize) {
+        for _ in 0..limit {
+            match self.rx.try_recv() {
+                Ok(BleEvent::Payload(data)) => self.pending.extend_from_slice(&data),
+                Ok(BleEvent::Notification(uuid, value)) => {
+                    let Some(channel) = self.channels.iter().find(|c| c.uuid == uuid) else {
+                        continue;
+                    };
+                    match channel.decode(&value) {
+                        Ok(line) => {
+                            self.stats.frames += 1;
+                            self.lines.push(line);
+                        }
+                        Err(e) => {
+                            self.stats.decode_errors += 1;
+                            self.stats.last_error = Some(e);
+                        }
+                    }
+                }
+                Ok(BleEvent::Disconnected) => {
+                    self.connected = false;
+                    self.status = "BLE Connection Lost";
+                }
+                Ok(BleEvent::Scanning) => self.status = "Scanning for the BLE device",
+                Ok(BleEvent::Connecting) => self.status = "Connecting",
+                Ok(BleEvent::Connected(writer)) => {
+                    self.writer = writer;
+                    self.connected = true;
+                }
+                Err(mpsc::TryRecvError::Disconnected) => {
+                    self.connected = false;
+                    self.status = "BLE task stopped";
+                    break;
+                }
+                Err(mpsc::TryRecvError::Empty) => break,
+            }
+        }
+    }
+}
+
+impl crate::source::DataSource for BleSource {
+    fn open(&mut self) -> Result<(), crate::source::OpenError> {
+        self.poll_events(usize::MAX);
+        if !self.connected {
+            return Err(crate::source::OpenError::Unavailable(
+                self.status.to_string(),
+            ));
+        }
+        self.is_open = true;
+        Ok(())
+    }
+
+    fn is_open(&self) -> bool {
+        self.is_open
+    }
+
+    fn close(&mut self) {
+        self.is_open = false;
+    }
+
+    fn read(&mut self) -> crate::source::SourceEvent {
+        use crate::source::SourceEvent;
+
+        if !self.is_open {
+            return SourceEvent::Idle;
+        }
+
+        // Limit the number of BLE messages processed per poll to prevent UI lockups
+        const MAX_MESSAGES_PER_POLL: usize = 32;
+        self.poll_events(MAX_MESSAGES_PER_POLL);
+
+        // Hand over whatever arrived before the link dropped first
+        if !self.lines.is_empty() {
+            SourceEvent::Lines(std::mem::take(&mut self.lines))
+        } else if !self.pending.is_empty() {
+            SourceEvent::Bytes(std::mem::take(&mut self.pending))
+        } else if !self.connected {
+            self.is_open = false;
+            SourceEvent::Disconnected(self.status.to_string())
+        } else {
+            SourceEvent::Idle
+        }
+    }
+
+    fn write(&mut self, data: &[u8]) -> std::io::Result<()> {
+        let Some(ref writer) = self.writer else {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::Unsupported,
+                "This BLE device has no NUS RX characteristic to send input to",
+            ));
+        };
+        if !self.connected {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::NotConnected,
+                "BLE Connection Lost",
+            ));
+        }
+        self.rt
+            .block_on(writer.write(data))
+            .map_err(std::io::Error::other)
+    }
+
+    fn frame_stats(&self) -> Option<FrameStats> {
+        (!self.channels.is_empty()).then_some(self.stats)
+    }
+
+    fn describe(&self) -> String {
+        format!("BLE {}", self.label)
+    }
 }
@@ -0,0 +1,477 @@
+#![cfg(feature = "ble")]
+
+use btleplug::api::{Central, CentralEvent, Peripheral as _, PeripheralProperties, ScanFilter};
+use btleplug::platform::Adapter;
+use crossterm::{
+    event::{self, KeyCode, KeyModifiers},
+    execute,
+    terminal::{EnterAlternateScreen, LeaveAlternateScreen, disable_raw_mode, enable_raw_mode},
+};
+use futures::stream::StreamExt;
+use ratatui::{
+    Terminal,
+    backend::CrosstermBackend,
+    layout::Constraint,
+    style::{Color, Modifier, Style},
+    widgets::{Block, Borders, Cell, Row, Table},
+};
+use std::collections::{BTreeMap, HashMap};
+use std::error::Error;
+use std::fs::OpenOptions;
+use std::io::{self, BufWriter, Write};
+use std::sync::{Arc, Mutex, mpsc};
+use std::time::{Duration, Instant};
+use uuid::Uuid;
+
+use crate::ble::{BleOptions, first_adapter};
+use crate::framing::{FrameStats, format_readings};
+use crate::schema::Schema;
+use crate::serial::get_timestamp;
+use crate::source::{DataSource, OpenError, SourceEvent};
+
+/// An advertising device, as shown in the `--ble-adv` table.
+#[derive(Clone)]
+pub struct Device {
+    pub address: String,
+    pub name: Option<String>,
+    pub rssi: Option<i16>,
+    pub last_seen: Instant,
+    pub adverts: u64,
+    /// The latest decoded readings, or the raw payload when nothing decodes it.
+    pub data: String,
+    /// BTHome devices repeat each packet; the ID tells repeats apart.
+    bthome_packet: Option<u8>,
+}
+
+impl Device {
+    /// Prefix for the device's plotter channels: its name, or its address.
+    fn key(&self) -> String {
+        match self.name {
+            // Commas and colons would split the reading lines
+            Some(ref name) => name.replace([',', ':'], " ").trim().to_string(),
+            None => self.address.replace(':', ""),
+        }
+    }
+}
+
+/// Devices seen so far, by address; shared by the scan task and the table view.
+pub type Devices = Arc<Mutex<BTreeMap<String, Device>>>;
+
+enum AdvEvent {
+    /// A decoded advertisement, formatted like any other reading line.
+    Readings(String),
+    DecodeError(&'static str),
+    Stopped(String),
+}
+
+/// What `--ble-adv` decodes besides BTHome: manufacturer data from one
+/// company, described by a `--schema` file.
+struct Decoder {
+    company: Option<u16>,
+    schema: Option<Schema>,
+}
+
+/// Watches scan events, keeping the device table current and decoding BTHome
+/// service data and `--ble-company` manufacturer data into reading lines.
+async fn scan(
+    central: Adapter,
+    options: BleOptions,
+    decoder: Decoder,
+    devices: Devices,
+    tx: mpsc::Sender<AdvEvent>,
+) {
+    let result = async {
+        let mut events = central.events().await?;
+        central
+            .start_scan(ScanFilter {
+                services: options.service().into_iter().collect(),
+            })
+            .await?;
+
+        while let Some(event) = events.next().await {
+            let (id, service_data, manufacturer_data) = match event {
+                CentralEvent::DeviceDiscovered(id)
+                | CentralEvent::DeviceUpdated(id)
+                | CentralEvent::RssiUpdate { id, .. } => (id, None, None),
+                CentralEvent::ServiceDataAdvertisement { id, service_data } => {
+                    (id, Some(service_data), None)
+                }
+                CentralEvent::ManufacturerDataAdvertisement {
+                    id,
+                    manufacturer_data,
+                } => (id, None, Some(manufacturer_data)),
+                _ => continue,
+            };
+            let Ok(peripheral) = central.peripheral(&id).await else {
+                continue;
+            };
+            let Ok(Some(properties)) = peripheral.properties().await else {
+                continue;
+            };
+            if !options.matches(&id.to_string(), &properties) {
+                continue;
+            }
+
+            let mut devices = devices.lock().unwrap();
+            let address = properties.address.to_string();
+            let device = devices.entry(address.clone()).or_insert_with(|| Device {
+                address,
+                name: None,
+                rssi: None,
+                last_seen: Instant::now(),
+                adverts: 0,
+                data: raw_summary(&properties),
+                bthome_packet: None,
+            });
+            device.name = properties.local_name.clone().or(device.name.take());
+            device.rssi = properties.rssi.or(device.rssi);
+            device.last_seen = Instant::now();
+            if service_data.is_none() && manufacturer_data.is_none() {
+                continue;
+            }
+            device.adverts += 1;
+
+            let readings =
+                match decoder.decode(device, service_data.as_ref(), manufacturer_data.as_ref()) {
+                    Some(Ok(readings)) if !readings.is_empty() => readings,
+                    Some(Ok(_)) => continue,
+                    Some(Err(e)) => {
+                        if tx.send(AdvEvent::DecodeError(e)).is_err() {
+                            return Ok(());
+                        }
+                        continue;
+                    }
+                    None => {
+                        device.data = raw_summary(&properties);
+                        continue;
+                    }
+                };
+
+            device.data = format_readings(&readings);
+            let key = device.key();
+            let readings: Vec<(String, f64)> = readings
+                .into_iter()
+                .map(|(name, value)| (format!("{} {}", key, name), value))
+                .collect();
+            if tx
+                .send(AdvEvent::Readings(format_readings(&readings)))
+                .is_err()
+            {
+                return Ok(());
+            }
+        }
+        Ok::<_, btleplug::Error>(())
+    }
+    .await;
+
+    let reason = match result {
+        Ok(()) => "BLE scan ended".to_string(),
+        Err(e) => format!("BLE scan failed: {}", e),
+    };
+    tx.send(AdvEvent::Stopped(reason)).ok();
+}
+
+impl Decoder {
+    /// Readings from one advertisement; `None` if nothing here is decodable.
+    fn decode(
+        &self,
+        device: &mut Device,
+        service_data: Option<&HashMap<Uuid, Vec<u8>>>,
+        manufacturer_data: Option<&HashMap<u16, Vec<u8>>>,
+    ) -> Option<Result<Vec<(String, f64)>, &'static str>> {
+        if let Some(data) = service_data.and_then(|data| data.get(&crate::bthome::BTHOME_UUID)) {
+            return Some(crate::bthome::decode(data).map(|packet| {
+                if packet.id.is_some() && packet.id == device.bthome_packet {
+                    return Vec::new();
+                }
+                device.bthome_packet = packet.id;
+                packet.readings
+            }));
+        }
+
+        let schema = self.schema.as_ref()?;
+        let data = manufacturer_data?.get(&self.company?)?;
+        Some(schema.decode(data))
+    }
+}
+
+/// Undecoded advertisement data, so a `--schema` can be written for it.
+fn raw_summary(properties: &PeripheralProperties) -> String {
+    let hex = |data: &[u8]| {
+        data.iter()
+            .map(|b| format!("{:02x}", b))
+            .collect::<Vec<_>>()
+            .join(" ")
+    };
+    let mut parts: Vec<String> = properties
+        .manufacturer_data
+        .iter()
+        .map(|(company, data)| format!("mfr 0x{:04x}: {}", company, hex(data)))
+        .collect();
+    parts.extend(properties.service_data.iter().map(|(uuid, data)| {
+        let label = crate::gatt::short_uuid(uuid)
+            .map(|short| format!("{:04x}", short))
+            .unwrap_or_else(|| uuid.to_string());
+        format!("svc {}: {}", label, hex(data))
+    }));
+    parts.sort();
+    parts.join("  ")
+}
+
+/// [`DataSource`] over BLE advertisements (`--ble-adv`). Nothing is
+/// connected to; every decoded advertisement becomes a reading line whose
+/// channels are prefixed with the device name.
+pub struct AdvSource {
+    /// Runs the scan task; dropping it stops the scan.
+    _rt: tokio::runtime::Runtime,
+    rx: mpsc::Receiver<AdvEvent>,
+    devices: Devices,
+    is_open: bool,
+    /// Why the scan task stopped, if it did.
+    stopped: Option<String>,
+    stats: FrameStats,
+}
+
+impl AdvSource {
+    pub fn start(config: &crate::config::MergedConfig) -> Result<Self, Box<dyn Error>> {
+        let options = BleOptions::from_config(config)?;
+        let schema = match (config.schema.as_deref(), config.ble_company) {
+            (Some(path), Some(_)) => Some(Schema::load(path)?),
+            (None, None) => None,
+            (Some(_), None) => {
+                return Err("With --ble-adv, --schema needs --ble-company to pick the manufacturer data it describes".into());
+            }
+            (None, Some(_)) => {
+                return Err(
+                    "--ble-company needs a --schema describing the manufacturer data".into(),
+                );
+            }
+        };
+        let decoder = Decoder {
+            company: config.ble_company,
+            schema,
+        };
+
+        let rt = tokio::runtime::Builder::new_multi_thread()
+            .enable_all()
+            .build()?;
+        let central = rt.block_on(first_adapter())?;
+        let devices = Devices::default();
+        let (tx, rx) = mpsc::channel();
+        rt.spawn(scan(central, options, decoder, devices.clone(), tx));
+
+        Ok(Self {
+            _rt: rt,
+            rx,
+            devices,
+            is_open: false,
+            stopped: None,
+            stats: FrameStats {
+                framing: "ADV",
+                ..FrameStats::default()
+            },
+        })
+    }
+
+    pub fn devices(&self) -> Devices {
+        self.devices.clone()
+    }
+}
+
+impl DataSource for AdvSource {
+    fn open(&mut self) -> Result<(), OpenError> {
+        if let Some(ref reason) = self.stopped {
+            return Err(OpenError::Fatal(reason.clone().into()));
+        }
+        self.is_open = true;
+        Ok(())
+    }
+
+    fn is_open(&self) -> bool {
+        self.is_open
+    }
+
+    fn close(&mut self) {
+        self.is_open = false;
+    }
+
+    fn read(&mut self) -> SourceEvent {
+        if !self.is_open {
+            return SourceEvent::Idle;
+        }
+
+        // Limit the number of advertisements processed per poll to prevent UI lockups
+        const MAX_ADVERTS_PER_POLL: usize = 32;
+        let mut lines = Vec::new();
+        for _ in 0..MAX_ADVERTS_PER_POLL {
+            match self.rx.try_recv() {
+                Ok(AdvEvent::Readings(line)) => {
+                    self.stats.frames += 1;
+                    lines.push(line);
+                }
+                Ok(AdvEvent::DecodeError(e)) => {
+                    self.stats.decode_errors += 1;
+                    self.stats.last_error = Some(e);
+                }
+                Ok(AdvEvent::Stopped(reason)) => self.stopped = Some(reason),
+                Err(mpsc::TryRecvError::Disconnected) if self.stopped.is_none() => {
+                    self.stopped = Some("BLE scan stopped".to_string());
+                }
+                Err(_) => break,
+            }
+        }
+
+        if !lines.is_empty() {
+            SourceEvent::Lines(lines)
+        } else if let Some(ref reason) = self.stopped {
+            self.is_open = false;
+            SourceEvent::Disconnected(reason.clone())
+        } else {
+            SourceEvent::Idle
+        }
+    }
+
+    fn write(&mut self, _data: &[u8]) -> io::Result<()> {
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "--ble-adv only listens to advertisements",
+        ))
+    }
+
+    fn frame_stats(&self) -> Option<FrameStats> {
+        Some(self.stats)
+    }
+
+    fn describe(&self) -> String {
+        "BLE advertisements".to_string()
+    }
+}
+
+/// The `--ble-adv` replacement for the monitor: a live table of advertising
+/// devices, strongest first. `Ctrl+P` switches to the plotter.
+pub fn run_table_mode(
+    config: crate::config::MergedConfig,
+    mut source: Box<dyn DataSource>,
+    devices: &Devices,
+) -> Result<crate::AppExitState, Box<dyn Error>> {
+    if !source.is_open() {
+        match source.open() {
+            Ok(()) | Err(OpenError::Unavailable(_)) => {}
+            Err(OpenError::Fatal(e)) => return Err(e),
+        }
+    }
+
+    let mut log_writer = match config.log_file {
+        Some(ref log_path) => Some(BufWriter::new(
+            OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(log_path)
+                .map_err(|e| format!("Failed to open log file {}: {}", log_path, e))?,
+        )),
+        None => None,
+    };
+
+    enable_raw_mode()?;
+    let mut stdout = io::stdout();
+    execute!(stdout, EnterAlternateScreen)?;
+    let mut terminal = Terminal::new(CrosstermBackend::new(stdout))?;
+    let mut status: Option<String> = None;
+
+    let result = loop {
+        if event::poll(Duration::from_millis(100))?
+            && let event::Event::Key(key) = event::read()?
+        {
+            match key.code {
+                KeyCode::Char('q') | KeyCode::Esc => break crate::AppExitState::Quit,
+                KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                    break crate::AppExitState::Quit;
+                }
+                KeyCode::Char('p') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                    break crate::AppExitState::SwitchToPlotter { source };
+                }
+                _ => {}
+            }
+        }
+
+        if !source.is_open()
+            && let Err(OpenError::Fatal(e)) = source.reconnect()
+        {
+            status = Some(e.to_string());
+        }
+        loop {
+            match source.read() {
+                SourceEvent::Lines(lines) => {
+                    if let Some(ref mut writer) = log_writer {
+                        for line in &lines {
+                            writeln!(writer, "RX [{}]: {}", get_timestamp(), line).ok();
+                        }
+                    }
+                }
+                SourceEvent::Disconnected(reason) => status = Some(reason),
+                _ => break,
+            }
+        }
+
+        let mut rows: Vec<Device> = devices.lock().unwrap().values().cloned().collect();
+        rows.sort_by_key(|device| std::cmp::Reverse(device.rssi.unwrap_or(i16::MIN)));
+        let stats = source.frame_stats().unwrap_or_default();
+        let title = match status {
+            Some(ref status) => format!(" BLE advertisements: {} ", status),
+            None => format!(
+                " BLE advertisements: {} devices, {} decoded, {} errors (Ctrl+P plot, q quit) ",
+                rows.len(),
+                stats.frames,
+                stats.decode_errors
+            ),
+        };
+
+        terminal.draw(|frame| {
+            let header = Row::new(["Device", "Address", "RSSI", "Seen", "Adverts", "Data"])
+                .style(Style::default().add_modifier(Modifier::BOLD));
+            let rows = rows.iter().map(|device| {
+                let seen = device.last_seen.elapsed().as_secs();
+                let style = if seen > 30 {
+                    Style::default().fg(Color::DarkGray)
+                } else {
+                    Style::default()
+                };
+                Row::new([
+                    Cell::from(device.name.clone().unwrap_or_else(|| "-".to_string())),
+                    Cell::from(device.address.clone()),
+                    Cell::from(
+                        device
+                            .rssi
+                            .map(|rssi| format!("{} dBm", rssi))
+                            .unwrap_or_else(|| "-".to_string()),
+                    ),
+                    Cell::from(format!("{}s", seen)),
+                    Cell::from(device.adverts.to_string()),
+                    Cell::from(device.data.clone()),
+                ])
+                .style(style)
+            });
+            let table = Table::new(
+                rows,
+                [
+                    Constraint::Length(20),
+                    Constraint::Length(17),
+                    Constraint::Length(8),
+                    Constraint::Length(5),
+                    Constraint::Length(7),
+                    Constraint::Min(10),
+                ],
+            )
+            .header(header)
+            .block(Block::default().borders(Borders::ALL).title(title));
+            frame.render_widget(table, frame.area());
+        })?;
+    };
+
+    if let Some(ref mut writer) = log_writer {
+        writer.flush().ok();
+    }
+    disable_raw_mode().ok();
+    execute!(terminal.backend_mut(), LeaveAlternateScreen).ok();
+    Ok(result)
+}
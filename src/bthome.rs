@@ -0,0 +1,173 @@
+#![cfg(feature = "ble")]
+
+use uuid::Uuid;
+
+/// BTHome service data UUID `0xfcd2`, on the Bluetooth base UUID.
+pub const BTHOME_UUID: Uuid =
+    Uuid::from_u128((0xfcd2_u128 << 96) | crate::gatt::BLUETOOTH_BASE_UUID);
+
+/// A BTHome v2 object: ID, reading name, size in bytes, signedness and factor.
+type Object = (u8, &'static str, usize, bool, f64);
+
+/// Sensor and binary sensor objects from the BTHome v2 format. Objects must be
+/// known to be skipped, so decoding stops at the first unknown ID and keeps
+/// what came before it.
+const OBJECTS: &[Object] = &[
+    (0x00, "Packet", 1, false, 1.0),
+    (0x01, "Battery", 1, false, 1.0),
+    (0x02, "Temperature", 2, true, 0.01),
+    (0x03, "Humidity", 2, false, 0.01),
+    (0x04, "Pressure", 3, false, 0.01),
+    (0x05, "Illuminance", 3, false, 0.01),
+    (0x06, "Mass", 2, false, 0.01),
+    (0x07, "Mass", 2, false, 0.01),
+    (0x08, "Dewpoint", 2, true, 0.01),
+    (0x09, "Count", 1, false, 1.0),
+    (0x0a, "Energy", 3, false, 0.001),
+    (0x0b, "Power", 3, false, 0.01),
+    (0x0c, "Voltage", 2, false, 0.001),
+    (0x0d, "PM2.5", 2, false, 1.0),
+    (0x0e, "PM10", 2, false, 1.0),
+    (0x0f, "Generic", 1, false, 1.0),
+    (0x10, "Power On", 1, false, 1.0),
+    (0x11, "Opening", 1, false, 1.0),
+    (0x12, "CO2", 2, false, 1.0),
+    (0x13, "TVOC", 2, false, 1.0),
+    (0x14, "Moisture", 2, false, 0.01),
+    (0x15, "Battery Low", 1, false, 1.0),
+    (0x16, "Battery Charging", 1, false, 1.0),
+    (0x17, "Carbon Monoxide", 1, false, 1.0),
+    (0x18, "Cold", 1, false, 1.0),
+    (0x19, "Connectivity", 1, false, 1.0),
+    (0x1a, "Door", 1, false, 1.0),
+    (0x1b, "Garage Door", 1, false, 1.0),
+    (0x1c, "Gas", 1, false, 1.0),
+    (0x1d, "Heat", 1, false, 1.0),
+    (0x1e, "Light", 1, false, 1.0),
+    (0x1f, "Lock", 1, false, 1.0),
+    (0x20, "Moisture", 1, false, 1.0),
+    (0x21, "Motion", 1, false, 1.0),
+    (0x22, "Moving", 1, false, 1.0),
+    (0x23, "Occupancy", 1, false, 1.0),
+    (0x24, "Plug", 1, false, 1.0),
+    (0x25, "Presence", 1, false, 1.0),
+    (0x26, "Problem", 1, false, 1.0),
+    (0x27, "Running", 1, false, 1.0),
+    (0x28, "Safety", 1, false, 1.0),
+    (0x29, "Smoke", 1, false, 1.0),
+    (0x2a, "Sound", 1, false, 1.0),
+    (0x2b, "Tamper", 1, false, 1.0),
+    (0x2c, "Vibration", 1, false, 1.0),
+    (0x2d, "Window", 1, false, 1.0),
+    (0x2e, "Humidity", 1, false, 1.0),
+    (0x2f, "Moisture", 1, false, 1.0),
+    (0x3a, "Button", 1, false, 1.0),
+    (0x3c, "Dimmer", 2, false, 1.0),
+    (0x3d, "Count", 2, false, 1.0),
+    (0x3e, "Count", 4, false, 1.0),
+    (0x3f, "Rotation", 2, true, 0.1),
+    (0x40, "Distance", 2, false, 1.0),
+    (0x41, "Distance", 2, false, 0.1),
+    (0x42, "Duration", 3, false, 0.001),
+    (0x43, "Current", 2, false, 0.001),
+    (0x44, "Speed", 2, false, 0.01),
+    (0x45, "Temperature", 2, true, 0.1),
+    (0x46, "UV Index", 1, false, 0.1),
+    (0x47, "Volume", 2, false, 0.1),
+    (0x48, "Volume", 2, false, 1.0),
+    (0x49, "Flow Rate", 2, false, 0.001),
+    (0x4a, "Voltage", 2, false, 0.1),
+    (0x4b, "Gas", 3, false, 0.001),
+    (0x4c, "Gas", 4, false, 0.001),
+    (0x4d, "Energy", 4, false, 0.001),
+    (0x4e, "Volume", 4, false, 0.001),
+    (0x4f, "Water", 4, false, 0.001),
+    (0x50, "Timestamp", 4, false, 1.0),
+    (0x51, "Acceleration", 2, false, 0.001),
+    (0x52, "Gyroscope", 2, false, 0.001),
+    (0x55, "Volume Storage", 4, false, 0.001),
+    (0x56, "Conductivity", 2, false, 1.0),
+    (0x57, "Temperature", 1, true, 1.0),
+    (0x58, "Temperature", 1, true, 0.35),
+    (0x59, "Count", 1, true, 1.0),
+    (0x5a, "Count", 2, true, 1.0),
+    (0x5b, "Count", 4, true, 1.0),
+    (0x5c, "Power", 4, true, 0.01),
+    (0x5d, "Current", 2, true, 0.001),
+    (0x5e, "Direction", 2, false, 0.01),
+    (0x5f, "Precipitation", 2, false, 0.1),
+    (0x60, "Channel", 1, false, 1.0),
+    (0x61, "Rotational Speed", 2, false, 1.0),
+    (0xf0, "Device Type", 2, false, 1.0),
+    (0xf1, "Firmware", 4, false, 1.0),
+    (0xf2, "Firmware", 3, false, 1.0),
+];
+
+/// A decoded BTHome advertisement.
+pub struct Packet {
+    /// The packet ID object; devices repeat a packet with the same ID.
+    pub id: Option<u8>,
+    pub readings: Vec<(String, f64)>,
+}
+
+/// Decodes BTHome v2 service data (the bytes after the `0xfcd2` UUID).
+pub fn decode(data: &[u8]) -> Result<Packet, &'static str> {
+    let (&info, mut rest) = data.split_first().ok_or("empty BTHome payload")?;
+    if info >> 5 != 2 {
+        return Err("unsupported BTHome version");
+    }
+    if info & 1 != 0 {
+        return Err("encrypted BTHome payloads are not supported");
+    }
+
+    let mut packet = Packet {
+        id: None,
+        readings: Vec::new(),
+    };
+    let mut seen: Vec<&str> = Vec::new();
+    while let Some((&id, after)) = rest.split_first() {
+        // Text and raw objects carry their own length
+        if id == 0x53 || id == 0x54 {
+            let (&len, after) = after.split_first().ok_or("truncated BTHome object")?;
+            rest = after.get(len as usize..).ok_or("truncated BTHome object")?;
+            continue;
+        }
+
+        let Some(&(_, name, size, signed, factor)) = OBJECTS.iter().find(|object| object.0 == id)
+        else {
+            if packet.readings.is_empty() {
+                return Err("unknown BTHome object");
+            }
+            break;
+        };
+        let bytes = after.get(..size).ok_or("truncated BTHome object")?;
+        rest = &after[size..];
+
+        let raw = bytes
+            .iter()
+            .rev()
+            .fold(0u64, |acc, b| (acc << 8) | *b as u64);
+        let value = if signed {
+            // Sign-extend from the object's width
+            let shift = 64 - size as u32 * 8;
+            ((raw << shift) as i64 >> shift) as f64
+        } else {
+            raw as f64
+        };
+
+        if id == 0x00 {
+            packet.id = Some(raw as u8);
+            continue;
+        }
+        // Repeated objects (e.g. two temperature probes) get numbered
+        let count = seen.iter().filter(|seen| **seen == name).count();
+        seen.push(name);
+        let name = if count == 0 {
+            name.to_string()
+        } else {
+            format!("{} {}", name, count + 1)
+        };
+        packet.readings.push((name, value * factor));
+    }
+    Ok(packet)
+}
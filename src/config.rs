@@ -1,4 +1,4 @@
-use clap::{CommandFactory, Parser, ValueEnum};
+use clap::{ArgGroup, CommandFactory, Parser, Subcommand, ValueEnum};
 use clap_complete::Shell;
 use clap_complete_nushell::Nushell;
 use inline_colorization::*;
@@ -6,6 +6,7 @@ use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use std::fs;
 use std::path::PathBuf;
 use std::str::FromStr;
+use std::time::Duration;
 
 #[derive(Clone, Copy, Debug, ValueEnum)]
 pub enum GenShell {
@@ -17,6 +18,217 @@ pub enum GenShell {
     Nu,
 }
 
+#[derive(Subcommand)]
+pub enum Command {
+    /// Run an expect-style test script against the device
+    Test {
+        /// Test script (.toml)
+        script: PathBuf,
+
+        /// Where to write the JUnit XML report
+        #[arg(long, value_name = "FILE", default_value = "junit.xml")]
+        junit: PathBuf,
+    },
+}
+
+/// Output format of `--pipe`.
+#[derive(Clone, Copy, Debug, PartialEq, ValueEnum)]
+pub enum PipeFormat {
+    /// Timestamp column followed by one column per reading
+    Csv,
+    /// One JSON object per line
+    Json,
+}
+
+/// Which TCP clients of `--serve` may write to the shared device.
+#[derive(Clone, Copy, Debug, PartialEq, ValueEnum, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ServeWritePolicy {
+    /// Every connected client can write
+    All,
+    /// Only the longest-connected client can write; the rest are read-only
+    First,
+    /// Clients are read-only; only the local user can write
+    None,
+}
+
+/// How the byte stream is split into packets (`--framing`).
+#[derive(Clone, Copy, Debug, PartialEq, ValueEnum, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Framing {
+    /// Newline-terminated text
+    Lines,
+    /// COBS-encoded packets, each terminated by a zero byte
+    Cobs,
+    /// SLIP (RFC 1055) packets
+    Slip,
+    /// `[sync][len][payload][crc]` packets, see --frame-sync/--frame-len/--frame-crc
+    Length,
+}
+
+/// Width and byte order of the length field of `--framing length`.
+#[derive(Clone, Copy, Debug, PartialEq, ValueEnum, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LengthField {
+    U8,
+    U16le,
+    U16be,
+    U32le,
+    U32be,
+}
+
+/// Checksum that ends each `--framing length` packet.
+#[derive(Clone, Copy, Debug, PartialEq, ValueEnum, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum FrameCrc {
+    None,
+    /// CRC-8/SMBUS
+    Crc8,
+    /// CRC-16/CCITT-FALSE
+    Crc16,
+    /// CRC-16/MODBUS
+    Crc16Modbus,
+    /// CRC-32 (zlib)
+    Crc32,
+}
+
+/// How a framed packet becomes readings (`--payload`).
+#[derive(Clone, Copy, Debug, PartialEq, ValueEnum, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PayloadFormat {
+    /// UTF-8 text, parsed like a received line
+    Text,
+    /// Packed little-endian f32 values, one channel each
+    F32le,
+}
+
+/// Lowest defmt log level that is shown (`--min-level`).
+#[derive(Clone, Copy, Debug, PartialEq, PartialOrd, ValueEnum, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DefmtLevel {
+    Trace,
+    Debug,
+    Info,
+    Warn,
+    Error,
+}
+
+/// How one RTT up-channel is decoded (`--rtt-channel N=DECODER`).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum RttDecoder {
+    Defmt,
+    Text,
+    Framed(Framing),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct RttChannelSpec {
+    pub channel: usize,
+    pub decoder: RttDecoder,
+}
+
+impl FromStr for RttChannelSpec {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let usage = || {
+            format!(
+                "Invalid RTT channel '{}', expected N=DECODER with DECODER one of defmt, text, cobs, slip or length",
+                s
+            )
+        };
+        let (channel, decoder) = s.split_once('=').ok_or_else(usage)?;
+        let channel = channel.trim().parse().map_err(|_| usage())?;
+        let decoder = match decoder.trim().to_lowercase().as_str() {
+            "defmt" => RttDecoder::Defmt,
+            "text" => RttDecoder::Text,
+            "cobs" => RttDecoder::Framed(Framing::Cobs),
+            "slip" => RttDecoder::Framed(Framing::Slip),
+            "length" => RttDecoder::Framed(Framing::Length),
+            _ => return Err(usage()),
+        };
+        Ok(RttChannelSpec { channel, decoder })
+    }
+}
+
+/// How a GATT characteristic's notifications are decoded (`--ble-char`).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum GattFormat {
+    /// The Bluetooth SIG definition if the UUID has one, otherwise text
+    Auto,
+    /// The Bluetooth SIG definition of the characteristic (e.g. Heart Rate)
+    Sig,
+    Text,
+    U8,
+    I8,
+    U16,
+    I16,
+    U32,
+    I32,
+    F32,
+    F64,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct BleCharSpec {
+    /// Full or 16-bit UUID, checked when the BLE source starts.
+    pub uuid: String,
+    pub format: GattFormat,
+    pub name: Option<String>,
+}
+
+impl FromStr for BleCharSpec {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let usage = || {
+            format!(
+                "Invalid BLE characteristic '{}', expected UUID[=FORMAT[:NAME]] with FORMAT one of sig, text, u8, i8, u16, i16, u32, i32, f32 or f64",
+                s
+            )
+        };
+        let (uuid, rest) = match s.split_once('=') {
+            Some((uuid, rest)) => (uuid, Some(rest)),
+            None => (s, None),
+        };
+        let (format, name) = match rest.map(|rest| rest.split_once(':').unwrap_or((rest, ""))) {
+            Some((format, name)) => (format.trim().to_lowercase(), name.trim()),
+            None => ("auto".to_string(), ""),
+        };
+        let format = match format.as_str() {
+            "auto" => GattFormat::Auto,
+            "sig" => GattFormat::Sig,
+            "text" => GattFormat::Text,
+            "u8" => GattFormat::U8,
+            "i8" => GattFormat::I8,
+            "u16" => GattFormat::U16,
+            "i16" => GattFormat::I16,
+            "u32" => GattFormat::U32,
+            "i32" => GattFormat::I32,
+            "f32" => GattFormat::F32,
+            "f64" => GattFormat::F64,
+            _ => return Err(usage()),
+        };
+        if uuid.trim().is_empty() {
+            return Err(usage());
+        }
+        Ok(BleCharSpec {
+            uuid: uuid.trim().to_string(),
+            format,
+            name: (!name.is_empty()).then(|| name.to_string()),
+        })
+    }
+}
+
+/// Bluetooth SIG company identifier, in hex (`0x0059`) or decimal.
+fn parse_company_id(s: &str) -> Result<u16, String> {
+    match s.strip_prefix("0x").or_else(|| s.strip_prefix("0X")) {
+        Some(hex) => u16::from_str_radix(hex, 16),
+        None => s.parse(),
+    }
+    .map_err(|_| format!("Invalid company ID '{}', expected e.g. 0x0059", s))
+}
+
 // Removed ValueEnum, Serialize, and Deserialize derive macros
 #[derive(Clone, Debug, PartialEq)]
 pub enum BrailleModel {
@@ -94,6 +306,7 @@ pub struct Config {
     pub export_limit: Option<usize>,
     pub plot_title: Option<String>,
     pub simulate: Option<bool>,
+    pub scenario: Option<String>,
     pub csv_file: Option<String>,
     pub replay_file: Option<String>,
     pub hex_mode: Option<bool>,
@@ -101,6 +314,29 @@ pub struct Config {
     pub obj_file: Option<String>,
     pub braille: Option<BrailleModel>,
     pub ble: Option<bool>,
+    pub serve: Option<String>,
+    pub serve_write: Option<ServeWritePolicy>,
+    pub script: Option<String>,
+    pub framing: Option<Framing>,
+    pub payload: Option<PayloadFormat>,
+    pub frame_sync: Option<String>,
+    pub frame_len: Option<LengthField>,
+    pub frame_crc: Option<FrameCrc>,
+    pub schema: Option<String>,
+    pub defmt_elf: Option<String>,
+    pub defmt_location: Option<bool>,
+    pub min_level: Option<DefmtLevel>,
+    pub defmt_modules: Option<Vec<String>>,
+    pub defmt_exclude: Option<Vec<String>>,
+    pub rtt_core: Option<usize>,
+    pub attach_under_reset: Option<bool>,
+    pub reset_halt: Option<bool>,
+    pub ble_name: Option<String>,
+    pub ble_address: Option<String>,
+    pub ble_service: Option<String>,
+    pub ble_scan_secs: Option<u64>,
+    pub ble_adv: Option<bool>,
+    pub ble_company: Option<u16>,
 }
 
 impl Default for Config {
@@ -122,6 +358,7 @@ impl Default for Config {
             export_limit: Some(1_000_000), // Defaults to 1 million points per sensor
             plot_title: None,
             simulate: Some(false),
+            scenario: None,
             csv_file: None,
             replay_file: None,
             hex_mode: Some(false),
@@ -129,6 +366,29 @@ impl Default for Config {
             obj_file: None,
             braille: Some(BrailleModel::Cube),
             ble: Some(false),
+            serve: None,
+            serve_write: Some(ServeWritePolicy::First),
+            script: None,
+            framing: Some(Framing::Lines),
+            payload: Some(PayloadFormat::Text),
+            frame_sync: None,
+            frame_len: Some(LengthField::U8),
+            frame_crc: Some(FrameCrc::None),
+            schema: None,
+            defmt_elf: None,
+            defmt_location: Some(false),
+            min_level: None,
+            defmt_modules: None,
+            defmt_exclude: None,
+            rtt_core: Some(0),
+            attach_under_reset: Some(false),
+            reset_halt: Some(false),
+            ble_name: None,
+            ble_address: None,
+            ble_service: None,
+            ble_scan_secs: Some(4),
+            ble_adv: Some(false),
+            ble_company: None,
         }
     }
 }
@@ -138,16 +398,25 @@ impl Default for Config {
     name = "comchan",
     version,
     author = "Vaishnav-Sabari-Girish",
-    about = "Blazingly Fast Minimal Serial Monitor with Plotting"
+    about = "Blazingly Fast Minimal Serial Monitor with Plotting",
+    group(ArgGroup::new("headless_mode").args(["headless", "pipe"]).multiple(true))
 )]
 pub struct Args {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+
     #[arg(long = "completions", value_enum, help = "Generate Shell completions")]
     pub completions: Option<GenShell>,
 
-    #[arg(short = 'p', long = "port", help = "Serial port(s) to connect to", num_args = 1..=2)]
+    #[arg(short = 'p', long = "port", global = true, help = "Serial port(s) to connect to, tcp://host:port / rfc2217://host:port for a network bridge, or - for stdin", num_args = 1..=2)]
     pub port: Option<Vec<String>>,
 
-    #[arg(short = 'r', long = "baud", help = "Baud Rate of the Serial Monitor")]
+    #[arg(
+        short = 'r',
+        long = "baud",
+        global = true,
+        help = "Baud Rate of the Serial Monitor"
+    )]
     pub baud: Option<u32>,
 
     #[arg(short = 'd', long = "data-bits")]
@@ -168,7 +437,12 @@ pub struct Args {
     #[arg(long = "reset-delay")]
     pub reset_delay_ms: Option<u64>,
 
-    #[arg(short = 'l', long = "log", help = "Log Serial data into a file")]
+    #[arg(
+        short = 'l',
+        long = "log",
+        global = true,
+        help = "Log Serial data into a file"
+    )]
     pub log_file: Option<String>,
 
     #[arg(long = "list-ports", action = clap::ArgAction::SetTrue, help = "List all available ports")]
@@ -186,7 +460,12 @@ pub struct Args {
     #[arg(long = "plot-points")]
     pub plot_points: Option<usize>,
 
-    #[arg(long = "config", short = 'c', help = "Path to config file")]
+    #[arg(
+        long = "config",
+        short = 'c',
+        global = true,
+        help = "Path to config file"
+    )]
     pub config_file: Option<PathBuf>,
 
     #[arg(long = "generate-config", action = clap::ArgAction::SetTrue)]
@@ -210,6 +489,13 @@ pub struct Args {
     #[arg(long = "simulate", action = clap::ArgAction::SetTrue, help = "Simulate Serial Data with no need for hardware (Use for testing ComChan)")]
     pub simulate: bool,
 
+    #[arg(
+        long = "scenario",
+        value_name = "FILE",
+        help = "Simulate from a scenario .toml instead of the built-in demo data (implies --simulate)"
+    )]
+    pub scenario: Option<String>,
+
     #[arg(
         long = "csv",
         help = "Export numeric data to a CSV file while streaming serial data"
@@ -248,14 +534,282 @@ pub struct Args {
     )]
     pub rtt: bool,
 
-    #[arg(long, requires = "rtt", help = "The Path to the compiled .elf file")]
+    #[arg(
+        long,
+        help = "The Path to the compiled .elf file (RTT firmware, or symbols for ESP panic backtraces)"
+    )]
     pub elf: Option<String>,
 
     #[arg(long, requires = "rtt", help = "Chip name for probe-rs")]
     pub chip: Option<String>,
 
+    #[arg(
+        long = "rtt-channel",
+        requires = "rtt",
+        value_name = "N=DECODER",
+        help = "Decode RTT up-channel N as defmt, text, cobs, slip or length (repeatable) [default: 0=defmt, others text]"
+    )]
+    pub rtt_channels: Vec<RttChannelSpec>,
+
+    #[arg(
+        long = "rtt-down",
+        requires = "rtt",
+        value_name = "N",
+        default_value_t = 0,
+        help = "RTT down-channel that typed input is written to"
+    )]
+    pub rtt_down: usize,
+
+    #[arg(
+        long,
+        requires = "rtt",
+        value_name = "VID:PID[:SERIAL]",
+        help = "Debug probe to use when several are connected (hex IDs, e.g. 1366:1051:000683000000)"
+    )]
+    pub probe: Option<probe_rs::probe::DebugProbeSelector>,
+
+    #[arg(
+        long,
+        default_value_t = false,
+        requires = "rtt",
+        help = "Program the --elf, reset the core, then attach RTT"
+    )]
+    pub flash: bool,
+
+    #[arg(
+        long = "core",
+        requires = "rtt",
+        value_name = "N",
+        help = "Core that runs the RTT firmware on multicore chips [default: 0]"
+    )]
+    pub rtt_core: Option<usize>,
+
+    #[arg(
+        long = "attach-under-reset",
+        requires = "rtt",
+        help = "Hold the target in reset while the probe attaches"
+    )]
+    pub attach_under_reset: bool,
+
+    #[arg(
+        long = "reset-halt",
+        requires = "rtt",
+        help = "Reset the core and run it to main, attaching RTT there before resuming so boot logs are not lost"
+    )]
+    pub reset_halt: bool,
+
     #[arg(long, action = clap::ArgAction::SetTrue, help = "Scan and connect to a BLE device for telemetry streaming")]
     pub ble: bool,
+
+    #[arg(
+        long = "ble-name",
+        value_name = "NAME",
+        help = "Connect to the first BLE device whose name contains NAME"
+    )]
+    pub ble_name: Option<String>,
+
+    #[arg(
+        long = "ble-address",
+        value_name = "ADDR",
+        help = "Connect to the BLE device with this address (or platform ID on macOS)"
+    )]
+    pub ble_address: Option<String>,
+
+    #[arg(
+        long = "ble-service",
+        value_name = "UUID",
+        help = "Only consider BLE devices advertising this service (full or 16-bit UUID)"
+    )]
+    pub ble_service: Option<String>,
+
+    #[arg(
+        long = "ble-scan",
+        value_name = "SECS",
+        help = "How long to scan for BLE devices [default: 4]"
+    )]
+    pub ble_scan_secs: Option<u64>,
+
+    #[arg(
+        long = "ble-char",
+        value_name = "UUID[=FORMAT[:NAME]]",
+        help = "Subscribe to this GATT characteristic instead of NUS (repeatable); FORMAT is sig, text, u8..i32, f32 or f64 [default: sig for known UUIDs, else text]"
+    )]
+    pub ble_chars: Vec<BleCharSpec>,
+
+    #[arg(
+        long = "ble-list",
+        requires = "ble",
+        help = "Connect to the BLE device, list its services and characteristics, and exit"
+    )]
+    pub ble_list: bool,
+
+    #[arg(
+        long = "ble-adv",
+        conflicts_with_all = ["ble_chars", "ble_list"],
+        help = "Scan BLE advertisements without connecting: live device table, BTHome v2 and --schema decoding"
+    )]
+    pub ble_adv: bool,
+
+    #[arg(
+        long = "ble-company",
+        value_name = "ID",
+        value_parser = parse_company_id,
+        help = "With --ble-adv, decode manufacturer data from this company ID (e.g. 0x0059) with --schema"
+    )]
+    pub ble_company: Option<u16>,
+
+    #[arg(
+        long = "serve",
+        value_name = "ADDR",
+        help = "Share the port with TCP clients listening on ADDR (e.g. 0.0.0.0:4000)"
+    )]
+    pub serve: Option<String>,
+
+    #[arg(
+        long = "serve-write",
+        value_enum,
+        help = "Which TCP clients may write to the device [default: first]"
+    )]
+    pub serve_write: Option<ServeWritePolicy>,
+
+    #[arg(
+        long = "virtual",
+        action = clap::ArgAction::SetTrue,
+        help = "Create a virtual serial device (Linux PTY) and monitor it through the real serial path"
+    )]
+    pub virtual_port: bool,
+
+    #[arg(
+        long = "virtual-script",
+        value_name = "FILE",
+        help = "Drive the virtual device from a script instead of the simulator (implies --virtual)"
+    )]
+    pub virtual_script: Option<String>,
+
+    #[arg(
+        long = "pipe",
+        value_enum,
+        num_args = 0..=1,
+        default_missing_value = "csv",
+        help = "Run headless and write parsed readings to stdout as CSV or JSON lines [default: csv]"
+    )]
+    pub pipe: Option<PipeFormat>,
+
+    #[arg(
+        long = "headless",
+        action = clap::ArgAction::SetTrue,
+        help = "Capture without the terminal UI and exit when a stop condition is met"
+    )]
+    pub headless: bool,
+
+    #[arg(
+        long = "duration",
+        value_parser = humantime::parse_duration,
+        requires = "headless_mode",
+        help = "Stop a headless run after this long (e.g. 30s, 5m)"
+    )]
+    pub duration: Option<Duration>,
+
+    #[arg(
+        long = "max-lines",
+        requires = "headless_mode",
+        help = "Stop a headless run after N received lines"
+    )]
+    pub max_lines: Option<usize>,
+
+    #[arg(
+        long = "until",
+        value_name = "REGEX",
+        value_parser = regex::Regex::new,
+        requires = "headless_mode",
+        help = "Stop a headless run successfully once a line matches REGEX"
+    )]
+    pub until: Option<regex::Regex>,
+
+    #[arg(
+        long = "script",
+        value_name = "FILE",
+        help = "Rhai script whose on_line/on_send hooks see every received and sent line"
+    )]
+    pub script: Option<String>,
+
+    #[arg(
+        long = "framing",
+        value_enum,
+        help = "Split the byte stream into packets instead of lines [default: lines]"
+    )]
+    pub framing: Option<Framing>,
+
+    #[arg(
+        long = "payload",
+        value_enum,
+        help = "Decoder for framed packets [default: text]"
+    )]
+    pub payload: Option<PayloadFormat>,
+
+    #[arg(
+        long = "frame-sync",
+        value_name = "HEX",
+        help = "Sync bytes that start each --framing length packet, e.g. AA55"
+    )]
+    pub frame_sync: Option<String>,
+
+    #[arg(
+        long = "frame-len",
+        value_enum,
+        help = "Length field of --framing length packets [default: u8]"
+    )]
+    pub frame_len: Option<LengthField>,
+
+    #[arg(
+        long = "frame-crc",
+        value_enum,
+        help = "Checksum after each --framing length packet [default: none]"
+    )]
+    pub frame_crc: Option<FrameCrc>,
+
+    #[arg(
+        long = "schema",
+        value_name = "FILE",
+        help = "Decode framed packets as packed structs described by a TOML schema (overrides --payload)"
+    )]
+    pub schema: Option<String>,
+
+    #[arg(
+        long = "defmt-elf",
+        value_name = "FILE",
+        conflicts_with = "rtt",
+        help = "Decode the byte stream as defmt logs using the table in this firmware ELF"
+    )]
+    pub defmt_elf: Option<String>,
+
+    #[arg(
+        long = "defmt-location",
+        help = "Show the module path and file:line of each defmt log"
+    )]
+    pub defmt_location: bool,
+
+    #[arg(
+        long = "min-level",
+        value_enum,
+        value_name = "LEVEL",
+        help = "Hide defmt logs below this level"
+    )]
+    pub min_level: Option<DefmtLevel>,
+
+    #[arg(
+        long = "defmt-module",
+        value_name = "PATH",
+        help = "Only show defmt logs from this module or its submodules (repeatable)"
+    )]
+    pub defmt_modules: Vec<String>,
+
+    #[arg(
+        long = "defmt-exclude",
+        value_name = "PATH",
+        help = "Hide defmt logs from this module or its submodules (repeatable)"
+    )]
+    pub defmt_exclude: Vec<String>,
 }
 
 /// The resolved, merged configuration used at runtime.
@@ -278,6 +832,7 @@ pub struct MergedConfig {
     pub export_limit: usize,
     pub plot_title: String,
     pub simulate: bool,
+    pub scenario: Option<String>,
     pub csv_file: Option<String>,
     pub replay_file: Option<String>,
     pub hex_mode: bool,
@@ -288,7 +843,51 @@ pub struct MergedConfig {
     pub rtt: bool,
     pub elf: Option<String>,
     pub chip: Option<String>,
+    pub rtt_channels: Vec<RttChannelSpec>,
+    pub rtt_down: usize,
+    pub probe: Option<probe_rs::probe::DebugProbeSelector>,
+    pub flash: bool,
+    pub rtt_core: usize,
+    pub attach_under_reset: bool,
+    pub reset_halt: bool,
     pub ble: bool,
+    // Only read by the `ble` feature
+    #[cfg_attr(not(feature = "ble"), allow(dead_code))]
+    pub ble_name: Option<String>,
+    #[cfg_attr(not(feature = "ble"), allow(dead_code))]
+    pub ble_address: Option<String>,
+    #[cfg_attr(not(feature = "ble"), allow(dead_code))]
+    pub ble_service: Option<String>,
+    #[cfg_attr(not(feature = "ble"), allow(dead_code))]
+    pub ble_scan_secs: u64,
+    #[cfg_attr(not(feature = "ble"), allow(dead_code))]
+    pub ble_chars: Vec<BleCharSpec>,
+    #[cfg_attr(not(feature = "ble"), allow(dead_code))]
+    pub ble_list: bool,
+    pub ble_adv: bool,
+    #[cfg_attr(not(feature = "ble"), allow(dead_code))]
+    pub ble_company: Option<u16>,
+    pub serve: Option<String>,
+    pub serve_write: ServeWritePolicy,
+    pub virtual_port: bool,
+    pub virtual_script: Option<String>,
+    pub pipe: Option<PipeFormat>,
+    pub headless: bool,
+    pub duration: Option<Duration>,
+    pub max_lines: Option<usize>,
+    pub until: Option<regex::Regex>,
+    pub script: Option<String>,
+    pub framing: Framing,
+    pub payload: PayloadFormat,
+    pub frame_sync: Option<String>,
+    pub frame_len: LengthField,
+    pub frame_crc: FrameCrc,
+    pub schema: Option<String>,
+    pub defmt_elf: Option<String>,
+    pub defmt_location: bool,
+    pub min_level: Option<DefmtLevel>,
+    pub defmt_modules: Vec<String>,
+    pub defmt_exclude: Vec<String>,
 }
 
 // Generate completions
@@ -415,8 +1014,24 @@ pub fn generate_default_config(path: Option<PathBuf>) -> Result<(), Box<dyn std:
 #
 # Command line arguments override these settings.
 # Set port = "auto" to auto-detect the first USB serial port.
+# Set port = "tcp://host:port" to connect to a ser2net / ESP-link bridge.
+# Set port = "rfc2217://host:port" for a terminal server with remote baud/parity control.
 # Parity:       "none" | "odd" | "even"
 # Flow control: "none" | "software" | "hardware"
+# Serve write:  "all" | "first" | "none"
+# Framing:      "lines" | "cobs" | "slip" | "length"
+# Frame len:    "u8" | "u16le" | "u16be" | "u32le" | "u32be"
+# Frame CRC:    "none" | "crc8" | "crc16" | "crc16-modbus" | "crc32"
+# Set schema = "path/to/packets.toml" to decode framed packets as packed structs.
+# Payload:      "text" | "f32le"
+# Set scenario = "path/to/scenario.toml" to drive --simulate from a scenario file.
+# Set script = "path/to/hooks.rhai" to run on_line/on_send hooks on every line.
+# Set defmt_elf = "path/to/firmware.elf" to decode defmt logs sent over any port.
+# Min level:    "trace" | "debug" | "info" | "warn" | "error"
+# Set defmt_modules = ["app::net"] / defmt_exclude = ["app::net::dns"] to filter defmt logs by module.
+# Set ble_name / ble_address / ble_service to connect to a BLE device without the picker.
+# Set ble_adv = true to scan advertisements instead; ble_company = 0x0059 picks the manufacturer data --schema decodes.
+# rtt_core, attach_under_reset and reset_halt tune how --rtt attaches (see --core, --attach-under-reset, --reset-halt).
 
 {toml_content}
 "#
@@ -469,7 +1084,8 @@ pub fn merge_config_and_args(config: Config, args: Args) -> MergedConfig {
             .plot_title
             .or(config.plot_title)
             .unwrap_or_else(|| "Sensor Data".to_string()),
-        simulate: args.simulate || config.simulate.unwrap_or(false),
+        simulate: args.simulate || args.scenario.is_some() || config.simulate.unwrap_or(false),
+        scenario: args.scenario.or(config.scenario),
         csv_file: args.csv_file.or(config.csv_file),
         replay_file: args.replay_file.or(config.replay_file),
         hex_mode: args.hex_mode.unwrap_or(false) || config.hex_mode.unwrap_or(false),
@@ -483,6 +1099,66 @@ pub fn merge_config_and_args(config: Config, args: Args) -> MergedConfig {
         rtt: args.rtt,
         elf: args.elf,
         chip: args.chip,
-        ble: args.ble || config.ble.unwrap_or(false),
+        rtt_channels: args.rtt_channels,
+        rtt_down: args.rtt_down,
+        probe: args.probe,
+        flash: args.flash,
+        rtt_core: args.rtt_core.or(config.rtt_core).unwrap_or(0),
+        attach_under_reset: args.attach_under_reset || config.attach_under_reset.unwrap_or(false),
+        reset_halt: args.reset_halt || config.reset_halt.unwrap_or(false),
+        // `--ble-adv` is a BLE mode of its own
+        ble: args.ble
+            || args.ble_adv
+            || config.ble.unwrap_or(false)
+            || config.ble_adv.unwrap_or(false),
+        ble_name: args.ble_name.or(config.ble_name),
+        ble_address: args.ble_address.or(config.ble_address),
+        ble_service: args.ble_service.or(config.ble_service),
+        ble_scan_secs: args.ble_scan_secs.or(config.ble_scan_secs).unwrap_or(4),
+        ble_chars: args.ble_chars,
+        ble_list: args.ble_list,
+        ble_adv: args.ble_adv || config.ble_adv.unwrap_or(false),
+        ble_company: args.ble_company.or(config.ble_company),
+        serve: args.serve.or(config.serve),
+        serve_write: args
+            .serve_write
+            .or(config.serve_write)
+            .unwrap_or(ServeWritePolicy::First),
+        virtual_port: args.virtual_port || args.virtual_script.is_some(),
+        virtual_script: args.virtual_script,
+        pipe: args.pipe,
+        headless: args.headless || args.pipe.is_some(),
+        duration: args.duration,
+        max_lines: args.max_lines,
+        until: args.until,
+        script: args.script.or(config.script),
+        framing: args.framing.or(config.framing).unwrap_or(Framing::Lines),
+        payload: args
+            .payload
+            .or(config.payload)
+            .unwrap_or(PayloadFormat::Text),
+        frame_sync: args.frame_sync.or(config.frame_sync),
+        frame_len: args
+            .frame_len
+            .or(config.frame_len)
+            .unwrap_or(LengthField::U8),
+        frame_crc: args
+            .frame_crc
+            .or(config.frame_crc)
+            .unwrap_or(FrameCrc::None),
+        schema: args.schema.or(config.schema),
+        defmt_elf: args.defmt_elf.or(config.defmt_elf),
+        defmt_location: args.defmt_location || config.defmt_location.unwrap_or(false),
+        min_level: args.min_level.or(config.min_level),
+        defmt_modules: if args.defmt_modules.is_empty() {
+            config.defmt_modules.unwrap_or_default()
+        } else {
+            args.defmt_modules
+        },
+        defmt_exclude: if args.defmt_exclude.is_empty() {
+            config.defmt_exclude.unwrap_or_default()
+        } else {
+            args.defmt_exclude
+        },
     }
 }
@@ -0,0 +1,294 @@
+use crate::config::{DefmtLevel, MergedConfig};
+use crate::framing::FrameStats;
+use crate::source::{DataSource, OpenError, SourceEvent};
+use defmt_decoder::{DecodeError, Frame, Location, Locations, StreamDecoder, Table};
+use std::error::Error;
+use std::io;
+use std::path::Path;
+
+/// Which defmt frames are shown and how (`--defmt-location`, `--min-level`,
+/// `--defmt-module`, `--defmt-exclude`).
+#[derive(Clone, Default)]
+pub struct DefmtOptions {
+    pub location: bool,
+    pub min_level: Option<DefmtLevel>,
+    pub modules: Vec<String>,
+    pub exclude: Vec<String>,
+}
+
+impl DefmtOptions {
+    pub fn from_config(config: &MergedConfig) -> Self {
+        Self {
+            location: config.defmt_location,
+            min_level: config.min_level,
+            modules: config.defmt_modules.clone(),
+            exclude: config.defmt_exclude.clone(),
+        }
+    }
+
+    fn needs_locations(&self) -> bool {
+        self.location || !self.modules.is_empty() || !self.exclude.is_empty()
+    }
+
+    fn keeps(&self, frame: &Frame, location: Option<&Location>) -> bool {
+        // `println!` frames have no level and always pass
+        if let (Some(min), Some(level)) = (self.min_level, frame.level()) {
+            let level = match level.as_str() {
+                "trace" => DefmtLevel::Trace,
+                "debug" => DefmtLevel::Debug,
+                "info" => DefmtLevel::Info,
+                "warn" => DefmtLevel::Warn,
+                _ => DefmtLevel::Error,
+            };
+            if level < min {
+                return false;
+            }
+        }
+
+        let module = location.map(|l| l.module.as_str());
+        let matches = |path: &String| {
+            module.is_some_and(|m| {
+                m == path
+                    || m.strip_prefix(path.as_str())
+                        .is_some_and(|rest| rest.starts_with("::"))
+            })
+        };
+        (self.modules.is_empty() || self.modules.iter().any(matches))
+            && !self.exclude.iter().any(matches)
+    }
+}
+
+/// Turns a defmt byte stream into formatted, level-coloured log lines.
+///
+/// The table is leaked so the stream decoder can borrow it for the lifetime of
+/// the program; it is loaded once per source.
+pub struct DefmtDecoder {
+    table: &'static Table,
+    stream_decoder: Box<dyn StreamDecoder + Send + Sync>,
+    options: DefmtOptions,
+    /// Source location of each log statement, keyed by frame index.
+    locations: Locations,
+    /// Decoded and malformed frames, shown like `--framing` counters.
+    stats: FrameStats,
+}
+
+impl DefmtDecoder {
+    pub fn load(elf_path: &str, options: DefmtOptions) -> Result<Self, Box<dyn Error>> {
+        let elf_bytes = std::fs::read(elf_path)
+            .map_err(|e| format!("Failed to read ELF '{}': {}", elf_path, e))?;
+        Self::from_elf(Box::leak(elf_bytes.into_boxed_slice()), options)
+    }
+
+    pub fn from_elf(
+        elf_bytes: &'static [u8],
+        options: DefmtOptions,
+    ) -> Result<Self, Box<dyn Error>> {
+        let table = Table::parse(elf_bytes)?
+            .ok_or("No defmt table found. Is the firmware compiled with defmt?")?;
+        let table: &'static Table = Box::leak(Box::new(table));
+
+        let locations = if options.needs_locations() {
+            let mut locations = table
+                .get_locations(elf_bytes)
+                .map_err(|e| format!("Failed to read defmt locations: {}", e))?;
+            if locations.is_empty() {
+                return Err("The ELF has no debug info for defmt locations. \
+                    Build the firmware with debug = 2 to use --defmt-location or module filters"
+                    .into());
+            }
+            let cwd = std::env::current_dir().ok();
+            for location in locations.values_mut() {
+                location.file = project_relative(&location.file, cwd.as_deref()).to_path_buf();
+            }
+            locations
+        } else {
+            Locations::new()
+        };
+
+        Ok(Self {
+            table,
+            stream_decoder: table.new_stream_decoder(),
+            options,
+            locations,
+            stats: FrameStats {
+                framing: "defmt",
+                ..Default::default()
+            },
+        })
+    }
+
+    /// Feeds received bytes and returns every log line completed by them.
+    pub fn received(&mut self, bytes: &[u8]) -> Vec<String> {
+        self.stream_decoder.received(bytes);
+
+        let mut logs = Vec::new();
+        loop {
+            match self.stream_decoder.decode() {
+                Ok(frame) => {
+                    self.stats.frames += 1;
+                    let location = self.locations.get(&frame.index());
+                    if self.options.keeps(&frame, location) {
+                        let location = location.filter(|_| self.options.location);
+                        logs.push(format_frame(&frame, location));
+                    }
+                }
+                // Partial frame; wait for more bytes
+                Err(DecodeError::UnexpectedEof) => break,
+                Err(DecodeError::Malformed) => {
+                    self.stats.decode_errors += 1;
+                    self.stats.last_error = Some("malformed defmt frame");
+                    // rzcobs resyncs on the next frame; raw encoding cannot
+                    if !self.table.encoding().can_recover() {
+                        break;
+                    }
+                }
+            }
+        }
+        logs
+    }
+
+    /// Drops any partial frame, e.g. after the link dropped mid-frame.
+    pub fn reset(&mut self) {
+        self.stream_decoder = self.table.new_stream_decoder();
+    }
+
+    /// A second decoder over the same table, e.g. for another RTT channel.
+    pub fn fresh(&self) -> Self {
+        Self {
+            table: self.table,
+            stream_decoder: self.table.new_stream_decoder(),
+            options: self.options.clone(),
+            locations: self.locations.clone(),
+            stats: FrameStats {
+                framing: "defmt",
+                ..Default::default()
+            },
+        }
+    }
+
+    pub fn stats(&self) -> FrameStats {
+        self.stats
+    }
+}
+
+/// Formats a frame as `timestamp [LEVEL] message` with an ANSI-coloured level,
+/// followed by `└─ module::path file.rs:123` when a location is given.
+pub fn format_frame(frame: &Frame, location: Option<&Location>) -> String {
+    let timestamp = frame
+        .display_timestamp()
+        .map(|t| t.to_string())
+        .unwrap_or_default();
+
+    let raw_level = frame
+        .level()
+        .map(|l| l.as_str().to_uppercase())
+        .unwrap_or_else(|| "UNK".to_string());
+    let padded_level = format!("{:5}", raw_level);
+
+    // Apply standard terminal ANSI color codes
+    let colored_level = match raw_level.as_str() {
+        "ERROR" => format!("\x1b[31;1m{}\x1b[0m", padded_level), // Bold Red
+        "WARN" => format!("\x1b[33;1m{}\x1b[0m", padded_level),  // Bold Yellow
+        "INFO" => format!("\x1b[32m{}\x1b[0m", padded_level),    // Green
+        "DEBUG" => format!("\x1b[36m{}\x1b[0m", padded_level),   // Cyan
+        "TRACE" => format!("\x1b[90m{}\x1b[0m", padded_level),   // Gray/Dim
+        _ => padded_level,
+    };
+
+    let mut message = frame.display_message().to_string();
+    if let Some(location) = location {
+        // Dimmed, and stripped again by the sensor parser
+        message.push_str(&format!(
+            " \x1b[90m└─ {} {}:{}\x1b[0m",
+            location.module,
+            location.file.display(),
+            location.line
+        ));
+    }
+
+    if timestamp.is_empty() {
+        format!("[{}] {}", colored_level, message)
+    } else {
+        format!("{} [{}] {}", timestamp, colored_level, message)
+    }
+}
+
+/// Source paths from an ELF's debug info are shown relative to `cwd` when
+/// they are inside the current project.
+pub fn project_relative<'a>(path: &'a Path, cwd: Option<&Path>) -> &'a Path {
+    cwd.and_then(|cwd| path.strip_prefix(cwd).ok())
+        .unwrap_or(path)
+}
+
+/// Wraps `source` so its bytes are decoded as defmt when `--defmt-elf` is set.
+pub fn wrap(
+    config: &MergedConfig,
+    source: Box<dyn DataSource>,
+) -> Result<Box<dyn DataSource>, Box<dyn Error>> {
+    let Some(ref elf) = config.defmt_elf else {
+        return Ok(source);
+    };
+    if config.framing != crate::config::Framing::Lines {
+        return Err("--defmt-elf frames the stream itself; drop --framing".into());
+    }
+    Ok(Box::new(DefmtSource {
+        inner: source,
+        decoder: DefmtDecoder::load(elf, DefmtOptions::from_config(config))?,
+    }))
+}
+
+/// [`DataSource`] adapter that decodes defmt from any byte-stream transport.
+struct DefmtSource {
+    inner: Box<dyn DataSource>,
+    decoder: DefmtDecoder,
+}
+
+impl DataSource for DefmtSource {
+    fn open(&mut self) -> Result<(), OpenError> {
+        self.inner.open()?;
+        self.decoder.reset();
+        Ok(())
+    }
+
+    fn is_open(&self) -> bool {
+        self.inner.is_open()
+    }
+
+    fn close(&mut self) {
+        self.inner.close();
+    }
+
+    fn read(&mut self) -> SourceEvent {
+        match self.inner.read() {
+            SourceEvent::Bytes(raw) => {
+                let lines = self.decoder.received(&raw);
+                if lines.is_empty() {
+                    SourceEvent::Idle
+                } else {
+                    SourceEvent::Lines(lines)
+                }
+            }
+            SourceEvent::Disconnected(e) => {
+                self.decoder.reset();
+                SourceEvent::Disconnected(e)
+            }
+            event => event,
+        }
+    }
+
+    fn write(&mut self, data: &[u8]) -> io::Result<()> {
+        self.inner.write(data)
+    }
+
+    fn set_dtr(&mut self, level: bool) -> io::Result<()> {
+        self.inner.set_dtr(level)
+    }
+
+    fn frame_stats(&self) -> Option<FrameStats> {
+        Some(self.decoder.stats())
+    }
+
+    fn describe(&self) -> String {
+        format!("{} (defmt)", self.inner.describe())
+    }
+}
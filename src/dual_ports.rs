@@ -1,5 +1,7 @@
 use crate::config::MergedConfig;
-use crate::serial::{parse_data_bits, parse_flow_control, parse_parity, parse_stop_bits};
+use crate::export::CsvStreamer;
+use crate::script::{LineScript, ScriptOutput};
+use crate::source::{OpenError, SerialOpen, SimProfile, SourceEvent};
 use crossterm::{
     event::{self, Event, KeyCode, KeyEventKind},
     execute,
@@ -17,7 +19,7 @@ use ratatui::{
     widgets::{Block, Borders, Paragraph, Scrollbar, ScrollbarOrientation, ScrollbarState},
 };
 use std::fs::OpenOptions;
-use std::io::{self, BufWriter, Read, Write};
+use std::io::{self, BufWriter, Write};
 use std::path::Path;
 use std::sync::mpsc;
 use std::thread;
@@ -37,6 +39,17 @@ pub enum DualEvent {
     Port2(String),
     Error1(String),
     Error2(String),
+    /// A typed command was written to the pane's port.
+    Sent1(String),
+    Sent2(String),
+}
+
+/// A line handed from the UI thread to a pane's thread, echoed back as `Sent`
+/// once it is on the wire.
+struct PaneWrite {
+    text: String,
+    /// `\r\n` for typed commands, `\r` for `--script` writes as in the other views.
+    ending: &'static str,
 }
 
 struct DualMonitorState {
@@ -89,7 +102,7 @@ fn spawn_serial_thread(
     port_name: String,
     cfg: MergedConfig,
     tx: mpsc::Sender<DualEvent>,
-    rx_cmd: mpsc::Receiver<String>,
+    rx_cmd: mpsc::Receiver<PaneWrite>,
     is_port1: bool,
 ) {
     thread::spawn(move || {
@@ -107,105 +120,199 @@ fn spawn_serial_thread(
                 DualEvent::Error2(err)
             }
         };
+        let wrap_sent = |cmd: String| {
+            if is_port1 {
+                DualEvent::Sent1(cmd)
+            } else {
+                DualEvent::Sent2(cmd)
+            }
+        };
 
-        if cfg.simulate {
-            let mut counter = 0;
-            loop {
-                while let Ok(cmd) = rx_cmd.try_recv() {
-                    let _ = tx.send(wrap_event(format!("TX: {}\n", cmd)));
-                }
-                let text = format!(
-                    "SIM [Port {}]: Packet {}\n",
-                    if is_port1 { 1 } else { 2 },
-                    counter
-                );
-                let _ = tx.send(wrap_event(text));
-                counter += 1;
-                thread::sleep(Duration::from_millis(500));
+        // Sources are not `Send`, so each pane builds its own inside the thread
+        let profile = SimProfile::Packets(if is_port1 { 1 } else { 2 });
+        let framed = crate::source::from_config(&cfg, &port_name, profile, SerialOpen::DUAL)
+            .and_then(|source| crate::framing::wrap(&cfg, source))
+            .and_then(|source| crate::defmt_stream::wrap(&cfg, source));
+        let mut source = match framed {
+            Ok(source) => source,
+            Err(e) => {
+                let _ = tx.send(wrap_error(format!("Config error: {}", e)));
+                return;
             }
-        } else {
-            let data_bits = match parse_data_bits(cfg.data_bits) {
-                Ok(v) => v,
-                Err(e) => {
-                    let _ = tx.send(wrap_error(format!("Config error: {}", e)));
-                    return;
-                }
-            };
-            let stop_bits = match parse_stop_bits(cfg.stop_bits) {
-                Ok(v) => v,
-                Err(e) => {
-                    let _ = tx.send(wrap_error(format!("Config error: {}", e)));
-                    return;
-                }
-            };
-            let parity = match parse_parity(&cfg.parity) {
-                Ok(v) => v,
-                Err(e) => {
-                    let _ = tx.send(wrap_error(format!("Config error: {}", e)));
-                    return;
-                }
-            };
-            let flow_control = match parse_flow_control(&cfg.flow_control) {
-                Ok(v) => v,
-                Err(e) => {
-                    let _ = tx.send(wrap_error(format!("Config error: {}", e)));
-                    return;
-                }
-            };
+        };
 
-            match serialport::new(&port_name, cfg.baud)
-                .timeout(Duration::from_millis(cfg.timeout_ms))
-                .data_bits(data_bits)
-                .stop_bits(stop_bits)
-                .parity(parity)
-                .flow_control(flow_control)
-                .open()
-            {
-                Ok(mut port) => {
-                    let _ = port.write_data_terminal_ready(true);
-                    let mut buffer = [0; 1024];
-                    loop {
-                        // Drain commands
-                        while let Ok(cmd) = rx_cmd.try_recv() {
-                            let payload = format!("{}\r\n", cmd);
-                            if let Err(e) = port
-                                .write_all(payload.as_bytes())
-                                .and_then(|_| port.flush())
-                            {
-                                let _ = tx.send(wrap_error(format!("Write Error: {}", e)));
-                            } else {
-                                let _ = tx.send(wrap_event(format!("TX: {}\n", cmd)));
-                            }
-                        }
+        let mut waiting_reported = false;
 
-                        match port.read(&mut buffer) {
-                            Ok(n) if n > 0 => {
-                                let _ = tx.send(wrap_event(
-                                    String::from_utf8_lossy(&buffer[..n]).into_owned(),
-                                ));
-                            }
-                            Err(e) if e.kind() != io::ErrorKind::TimedOut => {
-                                let _ = tx.send(wrap_error(format!("Read Error: {}", e)));
-                                break;
-                            }
-                            _ => {}
+        loop {
+            if !source.is_open() {
+                match source.reconnect() {
+                    Ok(()) => waiting_reported = false,
+                    Err(OpenError::Fatal(e)) => {
+                        let _ = tx.send(wrap_error(format!("Failed to open {}: {}", port_name, e)));
+                        return;
+                    }
+                    Err(OpenError::Unavailable(e)) => {
+                        if !waiting_reported {
+                            let _ =
+                                tx.send(wrap_error(format!("Failed to open {}: {}", port_name, e)));
+                            waiting_reported = true;
                         }
+                        // Writes typed during the outage are dropped, not sent on reconnect
+                        while let Ok(write) = rx_cmd.try_recv() {
+                            let _ = tx.send(wrap_error(format!(
+                                "Not sent, {} is not connected: {}",
+                                port_name, write.text
+                            )));
+                        }
+                        thread::sleep(Duration::from_secs(1));
+                        continue;
+                    }
+                }
+            }
+
+            // Drain writes handed over by the UI thread
+            while let Ok(write) = rx_cmd.try_recv() {
+                let payload = format!("{}{}", write.text, write.ending);
+                match source.write(payload.as_bytes()) {
+                    Ok(()) => {
+                        let _ = tx.send(wrap_sent(write.text));
+                    }
+                    Err(e) => {
+                        let _ = tx.send(wrap_error(format!("Write Error: {}", e)));
+                    }
+                }
+            }
+
+            match source.read() {
+                SourceEvent::Bytes(raw) => {
+                    let _ = tx.send(wrap_event(String::from_utf8_lossy(&raw).into_owned()));
+                }
+                SourceEvent::Lines(lines) | SourceEvent::ChannelLines(_, lines) => {
+                    for line in lines {
+                        let _ = tx.send(wrap_event(format!("{}\n", line)));
                     }
                 }
-                Err(e) => {
-                    let _ = tx.send(wrap_error(format!("Failed to open {}: {}", port_name, e)));
+                SourceEvent::Disconnected(e) => {
+                    let _ = tx.send(wrap_error(format!("Read Error: {}", e)));
                 }
+                SourceEvent::Idle | SourceEvent::Eof => thread::sleep(Duration::from_millis(10)),
             }
         }
     });
 }
 
+/// Applies a pane's `--script` side effects and shows its notes and alert in
+/// the pane. Writes are echoed once the pane's thread has sent them.
+fn apply_script_effects(
+    output: &ScriptOutput,
+    logs: &mut Vec<String>,
+    log_writer: &mut Option<BufWriter<std::fs::File>>,
+    tx_cmd: &mpsc::Sender<PaneWrite>,
+) {
+    logs.extend(output.logs.iter().map(|msg| format!("SCRIPT: {}", msg)));
+    let mut highlight = crate::script::log_effects(output, log_writer);
+    for text in &output.writes {
+        let write = PaneWrite {
+            text: text.clone(),
+            ending: "\r",
+        };
+        if tx_cmd.send(write).is_err() {
+            highlight = Some("Script write failed: pane thread has stopped".to_string());
+        }
+    }
+    if let Some(msg) = highlight {
+        logs.push(format!("ALERT: {}", msg));
+    }
+}
+
+/// Records one received line in a pane, passing it through the pane's
+/// `--script` hook first when there is one.
+fn receive_line(
+    line: String,
+    logs: &mut Vec<String>,
+    log_writer: &mut Option<BufWriter<std::fs::File>>,
+    csv: &mut Option<CsvStreamer>,
+    script: Option<&mut LineScript>,
+    tx_cmd: &mpsc::Sender<PaneWrite>,
+) {
+    let (line, readings) = match script {
+        Some(script) => {
+            let output = script.on_line(&line);
+            apply_script_effects(&output, logs, log_writer, tx_cmd);
+            (output.line, output.readings)
+        }
+        None => {
+            let readings = crate::parser::parse_sensor_data(&line);
+            (Some(line), readings)
+        }
+    };
+
+    if let Some(csv) = csv {
+        let _ = csv.write_row(&readings);
+    }
+    if let Some(line) = line {
+        if let Some(w) = log_writer {
+            let _ = writeln!(w, "{}", line);
+            let _ = w.flush();
+        }
+        logs.push(line);
+    }
+}
+
+/// Sends a typed command to a pane's port after the `--script` `on_send` hook.
+fn send_command(
+    cmd: String,
+    logs: &mut Vec<String>,
+    log_writer: &mut Option<BufWriter<std::fs::File>>,
+    csv: &mut Option<CsvStreamer>,
+    script: Option<&mut LineScript>,
+    tx_cmd: &mpsc::Sender<PaneWrite>,
+) {
+    let cmd = match script {
+        Some(script) => {
+            let output = script.on_send(&cmd);
+            apply_script_effects(&output, logs, log_writer, tx_cmd);
+            if let Some(csv) = csv
+                && !output.readings.is_empty()
+            {
+                let _ = csv.write_row(&output.readings);
+            }
+            match output.line {
+                Some(cmd) => cmd,
+                None => return,
+            }
+        }
+        None => cmd,
+    };
+    let write = PaneWrite {
+        text: cmd,
+        ending: "\r\n",
+    };
+    if tx_cmd.send(write).is_err() {
+        logs.push("ERROR: Write Error: pane thread has stopped".to_string());
+    }
+}
+
+/// Records a typed command or script write once the pane's thread has written
+/// it to its port.
+fn record_sent(
+    cmd: String,
+    logs: &mut Vec<String>,
+    log_writer: &mut Option<BufWriter<std::fs::File>>,
+) {
+    if let Some(w) = log_writer {
+        let _ = writeln!(w, "TX: {}", cmd);
+        let _ = w.flush();
+    }
+    logs.push(format!("TX: {}", cmd));
+}
+
 pub fn run_dual_mode(
     config: MergedConfig,
     ports: Vec<String>,
 ) -> Result<crate::AppExitState, Box<dyn std::error::Error>> {
-    let (tx_cmd1, rx_cmd1) = mpsc::channel::<String>();
-    let (tx_cmd2, rx_cmd2) = mpsc::channel::<String>();
+    let (tx_cmd1, rx_cmd1) = mpsc::channel::<PaneWrite>();
+    let (tx_cmd2, rx_cmd2) = mpsc::channel::<PaneWrite>();
 
     let port1_name = ports[0].clone();
     let port2_name = ports[1].clone();
@@ -234,8 +341,12 @@ pub fn run_dual_mode(
             .map(BufWriter::new)
     });
 
-    let mut csv1_streamer = csv1_path.and_then(|p| crate::export::CsvStreamer::new(&p).ok());
-    let mut csv2_streamer = csv2_path.and_then(|p| crate::export::CsvStreamer::new(&p).ok());
+    let mut csv1_streamer = csv1_path.and_then(|p| CsvStreamer::new(&p).ok());
+    let mut csv2_streamer = csv2_path.and_then(|p| CsvStreamer::new(&p).ok());
+
+    // Each pane gets its own instance so script state is per device
+    let mut script1 = config.script.as_deref().map(LineScript::load).transpose()?;
+    let mut script2 = config.script.as_deref().map(LineScript::load).transpose()?;
 
     let (tx, rx) = mpsc::channel::<DualEvent>();
 
@@ -275,15 +386,14 @@ pub fn run_dual_mode(
                         let line = buf1.drain(..=pos).collect::<String>();
                         let clean = line.trim_end().to_string();
                         if !clean.is_empty() {
-                            app_state.port1_logs.push(clean.clone());
-                            if let Some(ref mut w) = log1_writer {
-                                let _ = writeln!(w, "{}", clean);
-                                let _ = w.flush();
-                            }
-                            if let Some(ref mut csv) = csv1_streamer {
-                                let readings = crate::parser::parse_sensor_data(&clean);
-                                let _ = csv.write_row(&readings);
-                            }
+                            receive_line(
+                                clean,
+                                &mut app_state.port1_logs,
+                                &mut log1_writer,
+                                &mut csv1_streamer,
+                                script1.as_mut(),
+                                &tx_cmd1,
+                            );
                         }
                     }
                 }
@@ -293,21 +403,25 @@ pub fn run_dual_mode(
                         let line = buf2.drain(..=pos).collect::<String>();
                         let clean = line.trim_end().to_string();
                         if !clean.is_empty() {
-                            app_state.port2_logs.push(clean.clone());
-                            if let Some(ref mut w) = log2_writer {
-                                let _ = writeln!(w, "{}", clean);
-                                let _ = w.flush();
-                            }
-                            if let Some(ref mut csv) = csv2_streamer {
-                                let readings = crate::parser::parse_sensor_data(&clean);
-                                let _ = csv.write_row(&readings);
-                            }
+                            receive_line(
+                                clean,
+                                &mut app_state.port2_logs,
+                                &mut log2_writer,
+                                &mut csv2_streamer,
+                                script2.as_mut(),
+                                &tx_cmd2,
+                            );
                         }
                     }
                 }
-
                 DualEvent::Error1(err) => app_state.port1_logs.push(format!("ERROR: {}", err)),
                 DualEvent::Error2(err) => app_state.port2_logs.push(format!("ERROR: {}", err)),
+                DualEvent::Sent1(cmd) => {
+                    record_sent(cmd, &mut app_state.port1_logs, &mut log1_writer)
+                }
+                DualEvent::Sent2(cmd) => {
+                    record_sent(cmd, &mut app_state.port2_logs, &mut log2_writer)
+                }
             }
         }
 
@@ -343,9 +457,23 @@ pub fn run_dual_mode(
 
                         if !cmd.is_empty() {
                             if app_state.active_pane == 0 {
-                                let _ = tx_cmd1.send(cmd);
+                                send_command(
+                                    cmd,
+                                    &mut app_state.port1_logs,
+                                    &mut log1_writer,
+                                    &mut csv1_streamer,
+                                    script1.as_mut(),
+                                    &tx_cmd1,
+                                );
                             } else {
-                                let _ = tx_cmd2.send(cmd);
+                                send_command(
+                                    cmd,
+                                    &mut app_state.port2_logs,
+                                    &mut log2_writer,
+                                    &mut csv2_streamer,
+                                    script2.as_mut(),
+                                    &tx_cmd2,
+                                );
                             }
                         }
                     }
@@ -119,7 +119,7 @@ pub fn export_to_svg(
 }
 
 pub struct CsvStreamer {
-    writer: BufWriter<std::fs::File>,
+    writer: Box<dyn Write>,
     headers: Vec<String>,
     header_written: bool,
 }
@@ -131,11 +131,16 @@ impl CsvStreamer {
             .append(true)
             .open(filename)?;
 
-        Ok(Self {
-            writer: BufWriter::new(file),
+        Ok(Self::from_writer(Box::new(BufWriter::new(file))))
+    }
+
+    /// Streams rows to an arbitrary writer, e.g. stdout in `--pipe` mode.
+    pub fn from_writer(writer: Box<dyn Write>) -> Self {
+        Self {
+            writer,
             headers: Vec::new(),
             header_written: false,
-        })
+        }
     }
 
     pub fn write_row(&mut self, parsed_data: &[(String, f64)]) -> std::io::Result<()> {
@@ -0,0 +1,676 @@
+use crate::config::{FrameCrc, Framing, LengthField, MergedConfig, PayloadFormat};
+use crate::schema::Schema;
+use crate::source::{DataSource, OpenError, SourceEvent};
+use std::collections::VecDeque;
+use std::error::Error;
+use std::io;
+
+/// Frames longer than this without a delimiter are dropped as corrupt.
+const MAX_FRAME_LEN: usize = 4096;
+
+/// Running totals for a framed source, shown in the plotter status bar.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct FrameStats {
+    /// Name of the framing in use, e.g. `COBS`.
+    pub framing: &'static str,
+    /// Frames that were delimited and decoded successfully.
+    pub frames: u64,
+    /// Frames with a broken encoding, or too long to be real.
+    pub frame_errors: u64,
+    /// Well-formed frames whose payload could not be decoded.
+    pub decode_errors: u64,
+    /// Why the most recent frame was rejected.
+    pub last_error: Option<&'static str>,
+}
+
+/// Splits a byte stream into packets, one byte at a time.
+trait Deframer {
+    /// Returns a decoded packet, or why it was rejected, once a frame ends.
+    fn feed(&mut self, byte: u8) -> Option<Result<Vec<u8>, &'static str>>;
+    /// Further results completed by the last byte fed, if it finished more
+    /// than one frame.
+    fn next_queued(&mut self) -> Option<Result<Vec<u8>, &'static str>> {
+        None
+    }
+    /// Forgets any partial frame, e.g. after the link was re-opened.
+    fn reset(&mut self);
+    fn label(&self) -> &'static str;
+}
+
+/// Consistent Overhead Byte Stuffing: packets end in a zero byte and contain
+/// no other zeros.
+struct CobsDeframer {
+    buf: Vec<u8>,
+    /// False until the first delimiter, so a frame joined halfway is skipped.
+    synced: bool,
+}
+
+impl CobsDeframer {
+    fn new() -> Self {
+        Self {
+            buf: Vec::new(),
+            synced: false,
+        }
+    }
+}
+
+impl Deframer for CobsDeframer {
+    fn feed(&mut self, byte: u8) -> Option<Result<Vec<u8>, &'static str>> {
+        if byte == 0 {
+            let raw = std::mem::take(&mut self.buf);
+            if !self.synced {
+                self.synced = true;
+                return None;
+            }
+            return (!raw.is_empty()).then(|| cobs_decode(&raw));
+        }
+
+        if !self.synced {
+            return None;
+        }
+        self.buf.push(byte);
+        if self.buf.len() > MAX_FRAME_LEN {
+            self.reset();
+            return Some(Err("frame too long"));
+        }
+        None
+    }
+
+    fn reset(&mut self) {
+        self.buf.clear();
+        self.synced = false;
+    }
+
+    fn label(&self) -> &'static str {
+        "COBS"
+    }
+}
+
+fn cobs_decode(raw: &[u8]) -> Result<Vec<u8>, &'static str> {
+    let mut out = Vec::with_capacity(raw.len());
+    let mut i = 0;
+    while i < raw.len() {
+        let code = raw[i] as usize;
+        let end = i + code;
+        if end > raw.len() {
+            return Err("COBS block runs past the end of the frame");
+        }
+        out.extend_from_slice(&raw[i + 1..end]);
+        i = end;
+        // A full 254-byte block carries no implied zero
+        if code < 0xFF && i < raw.len() {
+            out.push(0);
+        }
+    }
+    Ok(out)
+}
+
+/// SLIP (RFC 1055): packets end in `END`, which is escaped inside the payload.
+struct SlipDeframer {
+    buf: Vec<u8>,
+    escaped: bool,
+    /// Set when the frame contained an invalid escape; reported at its end.
+    corrupt: bool,
+    /// False until the first `END`, so a frame joined halfway is skipped.
+    synced: bool,
+}
+
+const SLIP_END: u8 = 0xC0;
+const SLIP_ESC: u8 = 0xDB;
+const SLIP_ESC_END: u8 = 0xDC;
+const SLIP_ESC_ESC: u8 = 0xDD;
+
+impl SlipDeframer {
+    fn new() -> Self {
+        Self {
+            buf: Vec::new(),
+            escaped: false,
+            corrupt: false,
+            synced: false,
+        }
+    }
+}
+
+impl Deframer for SlipDeframer {
+    fn feed(&mut self, byte: u8) -> Option<Result<Vec<u8>, &'static str>> {
+        if byte == SLIP_END {
+            let frame = std::mem::take(&mut self.buf);
+            let corrupt = self.corrupt || self.escaped;
+            self.corrupt = false;
+            self.escaped = false;
+            if !self.synced {
+                self.synced = true;
+                return None;
+            }
+            // Senders often emit END before each packet as well; skip the empty frame
+            if frame.is_empty() && !corrupt {
+                return None;
+            }
+            return Some(if corrupt {
+                Err("invalid SLIP escape")
+            } else {
+                Ok(frame)
+            });
+        }
+
+        if !self.synced {
+            return None;
+        }
+        if self.escaped {
+            self.escaped = false;
+            match byte {
+                SLIP_ESC_END => self.buf.push(SLIP_END),
+                SLIP_ESC_ESC => self.buf.push(SLIP_ESC),
+                _ => self.corrupt = true,
+            }
+        } else if byte == SLIP_ESC {
+            self.escaped = true;
+        } else {
+            self.buf.push(byte);
+        }
+
+        if self.buf.len() > MAX_FRAME_LEN {
+            self.reset();
+            return Some(Err("frame too long"));
+        }
+        None
+    }
+
+    fn reset(&mut self) {
+        self.buf.clear();
+        self.escaped = false;
+        self.corrupt = false;
+        self.synced = false;
+    }
+
+    fn label(&self) -> &'static str {
+        "SLIP"
+    }
+}
+
+/// `[sync][len][payload][crc]` packets. The length counts payload bytes only;
+/// the CRC covers the length field and payload and is stored with the same
+/// byte order as the length field.
+struct LengthDeframer {
+    sync: Vec<u8>,
+    /// For each sync prefix, the length of its longest proper prefix that is
+    /// also a suffix, so an overlapping sync word is not lost on a mismatch.
+    sync_fallback: Vec<usize>,
+    len_field: LengthField,
+    crc: FrameCrc,
+    /// Sync bytes matched so far.
+    matched: usize,
+    /// Length field, payload and CRC of the current frame.
+    buf: Vec<u8>,
+    /// Bytes of a rejected frame still to be searched for a real sync word.
+    replay: VecDeque<u8>,
+    /// Results beyond the first one completed by the last byte fed.
+    queued: VecDeque<Result<Vec<u8>, &'static str>>,
+}
+
+impl LengthDeframer {
+    fn new(sync: Vec<u8>, len_field: LengthField, crc: FrameCrc) -> Self {
+        let mut sync_fallback = vec![0; sync.len()];
+        let mut k = 0;
+        for i in 1..sync.len() {
+            while k > 0 && sync[i] != sync[k] {
+                k = sync_fallback[k - 1];
+            }
+            if sync[i] == sync[k] {
+                k += 1;
+            }
+            sync_fallback[i] = k;
+        }
+        Self {
+            sync,
+            sync_fallback,
+            len_field,
+            crc,
+            matched: 0,
+            buf: Vec::new(),
+            replay: VecDeque::new(),
+            queued: VecDeque::new(),
+        }
+    }
+
+    fn step(&mut self, byte: u8) -> Option<Result<Vec<u8>, &'static str>> {
+        // Hunt for the sync bytes; anything before them is line noise
+        if self.matched < self.sync.len() {
+            while self.matched > 0 && byte != self.sync[self.matched] {
+                self.matched = self.sync_fallback[self.matched - 1];
+            }
+            if byte == self.sync[self.matched] {
+                self.matched += 1;
+            }
+            return None;
+        }
+
+        self.buf.push(byte);
+        let width = self.len_field.width();
+        if self.buf.len() < width {
+            return None;
+        }
+        let len = self.len_field.read(&self.buf[..width]);
+        if len > MAX_FRAME_LEN {
+            return Some(self.reject("length field exceeds 4 KiB"));
+        }
+        let body_len = width + len;
+        if self.buf.len() < body_len + self.crc.width() {
+            return None;
+        }
+
+        let (body, stored) = self.buf.split_at(body_len);
+        if !self.crc.matches(body, stored, self.len_field.big_endian()) {
+            return Some(self.reject("CRC mismatch"));
+        }
+        let payload = body[width..].to_vec();
+        self.buf.clear();
+        self.matched = 0;
+        Some(Ok(payload))
+    }
+
+    /// Drops the frame, but searches everything after its start again: the
+    /// sync word may have been matched inside a payload or in line noise, and
+    /// real frames can follow within the bytes consumed so far.
+    fn reject(&mut self, error: &'static str) -> Result<Vec<u8>, &'static str> {
+        let mut frame = self.sync.clone();
+        frame.append(&mut self.buf);
+        for &byte in frame[1..].iter().rev() {
+            self.replay.push_front(byte);
+        }
+        self.matched = 0;
+        Err(error)
+    }
+}
+
+impl Deframer for LengthDeframer {
+    fn feed(&mut self, byte: u8) -> Option<Result<Vec<u8>, &'static str>> {
+        let mut next = Some(byte);
+        while let Some(byte) = next {
+            if let Some(result) = self.step(byte) {
+                self.queued.push_back(result);
+            }
+            next = self.replay.pop_front();
+        }
+        self.queued.pop_front()
+    }
+
+    fn next_queued(&mut self) -> Option<Result<Vec<u8>, &'static str>> {
+        self.queued.pop_front()
+    }
+
+    fn reset(&mut self) {
+        self.buf.clear();
+        self.matched = 0;
+        self.replay.clear();
+        self.queued.clear();
+    }
+
+    fn label(&self) -> &'static str {
+        "LEN"
+    }
+}
+
+impl LengthField {
+    fn width(self) -> usize {
+        match self {
+            LengthField::U8 => 1,
+            LengthField::U16le | LengthField::U16be => 2,
+            LengthField::U32le | LengthField::U32be => 4,
+        }
+    }
+
+    fn big_endian(self) -> bool {
+        matches!(self, LengthField::U16be | LengthField::U32be)
+    }
+
+    fn read(self, bytes: &[u8]) -> usize {
+        read_uint(bytes, self.big_endian()) as usize
+    }
+}
+
+fn read_uint(bytes: &[u8], big_endian: bool) -> u64 {
+    let fold = |acc: u64, b: &u8| (acc << 8) | *b as u64;
+    if big_endian {
+        bytes.iter().fold(0, fold)
+    } else {
+        bytes.iter().rev().fold(0, fold)
+    }
+}
+
+impl FrameCrc {
+    fn width(self) -> usize {
+        match self {
+            FrameCrc::None => 0,
+            FrameCrc::Crc8 => 1,
+            FrameCrc::Crc16 | FrameCrc::Crc16Modbus => 2,
+            FrameCrc::Crc32 => 4,
+        }
+    }
+
+    fn matches(self, data: &[u8], stored: &[u8], big_endian: bool) -> bool {
+        let computed = match self {
+            FrameCrc::None => return true,
+            FrameCrc::Crc8 => crc8(data) as u64,
+            FrameCrc::Crc16 => crc16_ccitt(data) as u64,
+            FrameCrc::Crc16Modbus => crc16_modbus(data) as u64,
+            FrameCrc::Crc32 => crc32(data) as u64,
+        };
+        computed == read_uint(stored, big_endian)
+    }
+}
+
+/// CRC-8/SMBUS: poly 0x07, init 0.
+fn crc8(data: &[u8]) -> u8 {
+    data.iter().fold(0u8, |mut crc, &b| {
+        crc ^= b;
+        for _ in 0..8 {
+            crc = if crc & 0x80 != 0 {
+                (crc << 1) ^ 0x07
+            } else {
+                crc << 1
+            };
+        }
+        crc
+    })
+}
+
+/// CRC-16/CCITT-FALSE: poly 0x1021, init 0xFFFF.
+fn crc16_ccitt(data: &[u8]) -> u16 {
+    data.iter().fold(0xFFFFu16, |mut crc, &b| {
+        crc ^= (b as u16) << 8;
+        for _ in 0..8 {
+            crc = if crc & 0x8000 != 0 {
+                (crc << 1) ^ 0x1021
+            } else {
+                crc << 1
+            };
+        }
+        crc
+    })
+}
+
+/// CRC-16/MODBUS: reflected poly 0x8005, init 0xFFFF.
+fn crc16_modbus(data: &[u8]) -> u16 {
+    data.iter().fold(0xFFFFu16, |mut crc, &b| {
+        crc ^= b as u16;
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 {
+                (crc >> 1) ^ 0xA001
+            } else {
+                crc >> 1
+            };
+        }
+        crc
+    })
+}
+
+/// CRC-32/ISO-HDLC, as used by zlib and Ethernet.
+fn crc32(data: &[u8]) -> u32 {
+    !data.iter().fold(0xFFFF_FFFFu32, |mut crc, &b| {
+        crc ^= b as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 {
+                (crc >> 1) ^ 0xEDB8_8320
+            } else {
+                crc >> 1
+            };
+        }
+        crc
+    })
+}
+
+/// Parses `--frame-sync`, e.g. `AA55`, `aa 55` or `0xAA,0x55`.
+fn parse_hex_bytes(text: &str) -> Result<Vec<u8>, String> {
+    let digits: String = text
+        .split([' ', ',', ':'])
+        .map(|part| part.trim_start_matches("0x").trim_start_matches("0X"))
+        .collect();
+    if digits.is_empty() || !digits.len().is_multiple_of(2) {
+        return Err(format!(
+            "Invalid sync bytes '{}': expected hex pairs such as AA55",
+            text
+        ));
+    }
+    (0..digits.len())
+        .step_by(2)
+        .map(|i| {
+            u8::from_str_radix(&digits[i..i + 2], 16).map_err(|_| {
+                format!(
+                    "Invalid sync bytes '{}': expected hex pairs such as AA55",
+                    text
+                )
+            })
+        })
+        .collect()
+}
+
+/// Formats readings the way `parse_sensor_data` reads them back, so decoded
+/// packets flow through the same display, script, log and CSV path as text.
+pub fn format_readings(readings: &[(String, f64)]) -> String {
+    readings
+        .iter()
+        .map(|(name, value)| format!("{}: {}", name, format_value(*value)))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Six decimals are plenty for telemetry and hide float noise such as
+/// `12.340000000000002` from scaled integers or widened f32 values.
+fn format_value(value: f64) -> String {
+    if !value.is_finite() {
+        return value.to_string();
+    }
+    let text = format!("{:.6}", value);
+    text.trim_end_matches('0').trim_end_matches('.').to_string()
+}
+
+/// Turns a packet into a line of text.
+enum PayloadDecoder {
+    Format(PayloadFormat),
+    Schema(Schema),
+}
+
+fn decode_payload(decoder: &PayloadDecoder, payload: &[u8]) -> Result<String, &'static str> {
+    let format = match decoder {
+        PayloadDecoder::Format(format) => *format,
+        PayloadDecoder::Schema(schema) => {
+            return schema
+                .decode(payload)
+                .map(|readings| format_readings(&readings));
+        }
+    };
+    match format {
+        PayloadFormat::Text => std::str::from_utf8(payload)
+            .map(|text| text.trim_end_matches(['\r', '\n', '\0']).to_string())
+            .map_err(|_| "payload is not UTF-8"),
+        PayloadFormat::F32le => {
+            if payload.is_empty() || !payload.len().is_multiple_of(4) {
+                return Err("payload length is not a multiple of 4");
+            }
+            let readings: Vec<(String, f64)> = payload
+                .chunks_exact(4)
+                .enumerate()
+                .map(|(i, bytes)| {
+                    let value = f32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+                    (format!("Channel {}", i), value as f64)
+                })
+                .collect();
+            Ok(format_readings(&readings))
+        }
+    }
+}
+
+/// Splits a byte stream into packets and decodes each one into a line.
+pub struct FrameDecoder {
+    deframer: Box<dyn Deframer>,
+    decoder: PayloadDecoder,
+    stats: FrameStats,
+}
+
+impl FrameDecoder {
+    /// Builds the decoder for `framing`, taking the payload, schema and
+    /// length-framing options from `config`.
+    pub fn new(config: &MergedConfig, framing: Framing) -> Result<Self, Box<dyn Error>> {
+        let decoder = match config.schema {
+            Some(ref path) => PayloadDecoder::Schema(Schema::load(path)?),
+            None => PayloadDecoder::Format(config.payload),
+        };
+        let deframer: Box<dyn Deframer> = match framing {
+            Framing::Lines => return Err("newline framing has no packet decoder".into()),
+            Framing::Cobs => Box::new(CobsDeframer::new()),
+            Framing::Slip => Box::new(SlipDeframer::new()),
+            Framing::Length => {
+                let sync = match config.frame_sync {
+                    Some(ref text) => parse_hex_bytes(text)?,
+                    None => Vec::new(),
+                };
+                Box::new(LengthDeframer::new(
+                    sync,
+                    config.frame_len,
+                    config.frame_crc,
+                ))
+            }
+        };
+        let stats = FrameStats {
+            framing: deframer.label(),
+            ..Default::default()
+        };
+        Ok(Self {
+            deframer,
+            decoder,
+            stats,
+        })
+    }
+
+    /// Feeds received bytes and returns a line for every packet they complete.
+    pub fn received(&mut self, bytes: &[u8]) -> Vec<String> {
+        let mut lines = Vec::new();
+        for &byte in bytes {
+            let mut result = self.deframer.feed(byte);
+            while let Some(frame) = result {
+                self.record(frame, &mut lines);
+                result = self.deframer.next_queued();
+            }
+        }
+        lines
+    }
+
+    fn record(&mut self, frame: Result<Vec<u8>, &'static str>, lines: &mut Vec<String>) {
+        match frame {
+            Ok(payload) => match decode_payload(&self.decoder, &payload) {
+                Ok(line) => {
+                    self.stats.frames += 1;
+                    lines.push(line);
+                }
+                Err(e) => {
+                    self.stats.decode_errors += 1;
+                    self.stats.last_error = Some(e);
+                }
+            },
+            Err(e) => {
+                self.stats.frame_errors += 1;
+                self.stats.last_error = Some(e);
+            }
+        }
+    }
+
+    pub fn reset(&mut self) {
+        self.deframer.reset();
+    }
+
+    pub fn stats(&self) -> FrameStats {
+        self.stats
+    }
+}
+
+/// Wraps a byte-stream source and turns its bytes into one line per decoded
+/// packet (`--framing`). Sources that already deliver lines pass through.
+pub struct FramedSource {
+    inner: Box<dyn DataSource>,
+    frames: FrameDecoder,
+}
+
+/// Applies `--framing` to a source; newline framing leaves it untouched.
+pub fn wrap(
+    config: &MergedConfig,
+    source: Box<dyn DataSource>,
+) -> Result<Box<dyn DataSource>, Box<dyn Error>> {
+    if config.rtt {
+        // RTT frames each up-channel on its own, see `--rtt-channel`
+        if config.framing != Framing::Lines {
+            return Err("With --rtt, pick a framing per channel, e.g. --rtt-channel 1=cobs".into());
+        }
+        return Ok(source);
+    }
+    if config.ble_adv {
+        // Each advertisement is decoded on its own; --schema covers manufacturer data
+        if config.framing != Framing::Lines {
+            return Err("--ble-adv decodes whole advertisements; drop --framing".into());
+        }
+        return Ok(source);
+    }
+    if config.framing == Framing::Lines {
+        if config.schema.is_some() {
+            return Err(
+                "--schema decodes binary packets; choose a --framing (cobs, slip or length)".into(),
+            );
+        }
+        return Ok(source);
+    }
+
+    Ok(Box::new(FramedSource {
+        inner: source,
+        frames: FrameDecoder::new(config, config.framing)?,
+    }))
+}
+
+impl DataSource for FramedSource {
+    fn open(&mut self) -> Result<(), OpenError> {
+        self.inner.open()?;
+        self.frames.reset();
+        Ok(())
+    }
+
+    fn is_open(&self) -> bool {
+        self.inner.is_open()
+    }
+
+    fn close(&mut self) {
+        self.inner.close();
+    }
+
+    fn read(&mut self) -> SourceEvent {
+        match self.inner.read() {
+            SourceEvent::Bytes(raw) => {
+                let lines = self.frames.received(&raw);
+                if lines.is_empty() {
+                    SourceEvent::Idle
+                } else {
+                    SourceEvent::Lines(lines)
+                }
+            }
+            SourceEvent::Disconnected(e) => {
+                self.frames.reset();
+                SourceEvent::Disconnected(e)
+            }
+            event => event,
+        }
+    }
+
+    fn write(&mut self, data: &[u8]) -> io::Result<()> {
+        self.inner.write(data)
+    }
+
+    fn set_dtr(&mut self, level: bool) -> io::Result<()> {
+        self.inner.set_dtr(level)
+    }
+
+    fn frame_stats(&self) -> Option<FrameStats> {
+        Some(self.frames.stats())
+    }
+
+    fn describe(&self) -> String {
+        format!("{} ({})", self.inner.describe(), self.frames.stats.framing)
+    }
+}
@@ -0,0 +1,159 @@
+#![cfg(feature = "ble")]
+
+use crate::config::{BleCharSpec, GattFormat};
+use crate::framing::format_readings;
+use uuid::Uuid;
+
+/// Bluetooth base UUID `00000000-0000-1000-8000-00805f9b34fb`; 16-bit UUIDs
+/// fill in the top 32 bits.
+pub const BLUETOOTH_BASE_UUID: u128 = 0x0000_0000_0000_1000_8000_0080_5f9b_34fb;
+
+/// Characteristics the `sig` format decodes, by 16-bit UUID.
+const SIG_CHARACTERISTICS: &[(u16, &str)] = &[
+    (0x2a19, "Battery"),
+    // Health Thermometer's reading, kept apart from the environmental 0x2a6e
+    (0x2a1c, "Thermometer"),
+    (0x2a37, "HeartRate"),
+    (0x2a6d, "Pressure"),
+    (0x2a6e, "Temperature"),
+    (0x2a6f, "Humidity"),
+];
+
+/// The 16-bit assigned number of a UUID built on the Bluetooth base UUID.
+pub fn short_uuid(uuid: &Uuid) -> Option<u16> {
+    let value = uuid.as_u128();
+    if value & !(0xffff_ffff << 96) != BLUETOOTH_BASE_UUID || value >> 112 != 0 {
+        return None;
+    }
+    Some((value >> 96) as u16)
+}
+
+/// Name of a characteristic the `sig` format knows, e.g. `HeartRate`.
+pub fn sig_name(uuid: &Uuid) -> Option<&'static str> {
+    let short = short_uuid(uuid)?;
+    SIG_CHARACTERISTICS
+        .iter()
+        .find(|(id, _)| *id == short)
+        .map(|(_, name)| *name)
+}
+
+/// A subscribed characteristic and how its notifications become a line.
+#[derive(Clone, Debug)]
+pub struct GattChannel {
+    pub uuid: Uuid,
+    name: String,
+    format: GattFormat,
+}
+
+impl GattChannel {
+    pub fn from_spec(spec: &BleCharSpec) -> Result<Self, String> {
+        let uuid = crate::ble::parse_uuid(&spec.uuid)?;
+        let format = match spec.format {
+            GattFormat::Auto if sig_name(&uuid).is_some() => GattFormat::Sig,
+            GattFormat::Auto => GattFormat::Text,
+            GattFormat::Sig if sig_name(&uuid).is_none() => {
+                return Err(format!(
+                    "No SIG format is known for characteristic {}; pick one such as f32 or u16",
+                    spec.uuid
+                ));
+            }
+            format => format,
+        };
+        let name = spec.name.clone().unwrap_or_else(|| match sig_name(&uuid) {
+            Some(name) => name.to_string(),
+            None => match short_uuid(&uuid) {
+                Some(short) => format!("Char {:04x}", short),
+                None => format!("Char {}", &uuid.simple().to_string()[..8]),
+            },
+        });
+        Ok(Self { uuid, name, format })
+    }
+
+    /// Turns one notification into a line for the monitor and plotter.
+    pub fn decode(&self, value: &[u8]) -> Result<String, &'static str> {
+        let values = match self.format {
+            GattFormat::Text => {
+                return std::str::from_utf8(value)
+                    .map(|text| text.trim_end_matches(['\r', '\n', '\0']).to_string())
+                    .map_err(|_| "notification is not UTF-8");
+            }
+            GattFormat::Sig | GattFormat::Auto => vec![self.decode_sig(value)?],
+            GattFormat::U8 => numbers(value, 1, |b| b[0] as f64)?,
+            GattFormat::I8 => numbers(value, 1, |b| b[0] as i8 as f64)?,
+            GattFormat::U16 => numbers(value, 2, |b| u16::from_le_bytes([b[0], b[1]]) as f64)?,
+            GattFormat::I16 => numbers(value, 2, |b| i16::from_le_bytes([b[0], b[1]]) as f64)?,
+            GattFormat::U32 => numbers(value, 4, |b| {
+                u32::from_le_bytes([b[0], b[1], b[2], b[3]]) as f64
+            })?,
+            GattFormat::I32 => numbers(value, 4, |b| {
+                i32::from_le_bytes([b[0], b[1], b[2], b[3]]) as f64
+            })?,
+            GattFormat::F32 => numbers(value, 4, |b| {
+                f32::from_le_bytes([b[0], b[1], b[2], b[3]]) as f64
+            })?,
+            GattFormat::F64 => numbers(value, 8, |b| {
+                f64::from_le_bytes([b[0], b[1], b[2], b[3], b[4], b[5], b[6], b[7]])
+            })?,
+        };
+
+        // Several values in one notification become `Name 0`, `Name 1`, …
+        let readings: Vec<(String, f64)> = if values.len() == 1 {
+            vec![(self.name.clone(), values[0])]
+        } else {
+            values
+                .into_iter()
+                .enumerate()
+                .map(|(i, value)| (format!("{} {}", self.name, i), value))
+                .collect()
+        };
+        Ok(format_readings(&readings))
+    }
+
+    fn decode_sig(&self, value: &[u8]) -> Result<f64, &'static str> {
+        let too_short = "notification too short for its SIG format";
+        let byte = |i: usize| value.get(i).copied().ok_or(too_short);
+        let u16_at = |i: usize| Ok::<_, &str>(u16::from_le_bytes([byte(i)?, byte(i + 1)?]));
+
+        match short_uuid(&self.uuid) {
+            // Battery Level: percent
+            Some(0x2a19) => Ok(byte(0)? as f64),
+            // Temperature Measurement: flags, then an IEEE-11073 32-bit FLOAT;
+            // flags bit 0 marks °F, converted so the channel is always °C
+            Some(0x2a1c) => {
+                let raw = u32::from_le_bytes([byte(1)?, byte(2)?, byte(3)?, byte(4)?]);
+                // NaN, NRes, ±INFINITY and the reserved value
+                if (0x7f_fffe..=0x80_0002).contains(&(raw & 0xff_ffff)) {
+                    return Err("thermometer sent no valid reading");
+                }
+                let mantissa = ((raw << 8) as i32 >> 8) as f64;
+                let exponent = (raw >> 24) as i8 as i32;
+                let value = mantissa * 10f64.powi(exponent);
+                Ok(if byte(0)? & 1 == 0 {
+                    value
+                } else {
+                    (value - 32.0) * 5.0 / 9.0
+                })
+            }
+            // Heart Rate Measurement: flags bit 0 selects a u8 or u16 rate
+            Some(0x2a37) if byte(0)? & 1 == 0 => Ok(byte(1)? as f64),
+            Some(0x2a37) => Ok(u16_at(1)? as f64),
+            // Pressure: 0.1 Pa
+            Some(0x2a6d) => {
+                Ok(u32::from_le_bytes([byte(0)?, byte(1)?, byte(2)?, byte(3)?]) as f64 * 0.1)
+            }
+            // Temperature: 0.01 °C
+            Some(0x2a6e) => Ok(i16::from_le_bytes([byte(0)?, byte(1)?]) as f64 * 0.01),
+            // Humidity: 0.01 %
+            Some(0x2a6f) => Ok(u16_at(0)? as f64 * 0.01),
+            _ => Err("no SIG format for this characteristic"),
+        }
+    }
+}
+
+/// Splits a notification into little-endian values of `size` bytes each.
+fn numbers(value: &[u8], size: usize, read: fn(&[u8]) -> f64) -> Result<Vec<f64>, &'static str> {
+    if value.is_empty() || !value.len().is_multiple_of(size) {
+        return Err("notification length does not fit its format");
+    }
+    Ok(value.chunks_exact(size).map(read).collect())
+}
@@ -0,0 +1,240 @@
+use crate::config::MergedConfig;
+use crate::export::CsvStreamer;
+use crate::pipe::ReadingWriter;
+use crate::script::LineScript;
+use crate::serial::get_timestamp;
+use crate::source::{DataSource, OpenError, SourceEvent};
+use inline_colorization::*;
+use std::error::Error;
+use std::fs::OpenOptions;
+use std::io::{self, BufWriter, Write};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Process exit status of a headless run. 1 is left for runtime errors and
+/// 2 for command line usage errors, as reported by `main` and clap.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum HeadlessExit {
+    /// `--until` matched, or a limit was reached when no `--until` was given.
+    Done = 0,
+    /// A limit or the end of input was reached before `--until` matched.
+    UntilNotSeen = 3,
+    /// The device could not be opened at all before the run ended.
+    NeverConnected = 4,
+}
+
+/// Runs without raw mode, key reader or TUI until a stop condition is met.
+///
+/// Received lines are echoed to stdout, or with `--pipe` only the parsed
+/// readings are. Status messages always go to stderr. `--log` and `--csv`
+/// capture exactly as in the monitor.
+pub fn run_headless_mode(
+    config: MergedConfig,
+    mut source: Box<dyn DataSource>,
+) -> Result<HeadlessExit, Box<dyn Error>> {
+    let mut output = config.pipe.map(ReadingWriter::stdout);
+    let mut log_writer = match config.log_file {
+        Some(ref path) => Some(BufWriter::new(
+            OpenOptions::new().create(true).append(true).open(path)?,
+        )),
+        None => None,
+    };
+    let mut csv_streamer = match config.csv_file {
+        Some(ref path) => Some(CsvStreamer::new(path)?),
+        None => None,
+    };
+    let mut script = config.script.as_deref().map(LineScript::load).transpose()?;
+
+    let started = Instant::now();
+    let deadline = config.duration.map(|d| started + d);
+    let mut rx_buf = String::new();
+    let mut line_count: usize = 0;
+    let mut ever_connected = false;
+    let mut waiting_reported = false;
+
+    let outcome = 'run: loop {
+        if deadline.is_some_and(|d| Instant::now() >= d) {
+            eprintln!(
+                "{color_yellow}⏱️ Duration of {} reached after {} lines{color_reset}",
+                humantime::format_duration(config.duration.unwrap_or_default()),
+                line_count
+            );
+            break limit_outcome(&config, ever_connected);
+        }
+
+        if !source.is_open() {
+            match source.reconnect() {
+                Ok(()) => {
+                    eprintln!(
+                        "{color_green}🔌 Connected to {}{color_reset}",
+                        source.describe()
+                    );
+                    ever_connected = true;
+                    waiting_reported = false;
+                }
+                Err(OpenError::Fatal(e)) => return Err(e),
+                Err(OpenError::Unavailable(e)) => {
+                    if !waiting_reported {
+                        eprintln!(
+                            "{color_yellow}⏳ Waiting for {} ({})...{color_reset}",
+                            source.describe(),
+                            e
+                        );
+                        waiting_reported = true;
+                    }
+                    thread::sleep(Duration::from_millis(200));
+                    continue;
+                }
+            }
+        }
+
+        let mut lines = Vec::new();
+        let mut finished = false;
+
+        match source.read() {
+            SourceEvent::Bytes(raw) => {
+                rx_buf.push_str(&String::from_utf8_lossy(&raw));
+                while let Some(pos) = rx_buf.find('\n') {
+                    lines.push(rx_buf.drain(..=pos).collect::<String>());
+                }
+            }
+            SourceEvent::Lines(batch) | SourceEvent::ChannelLines(_, batch) => lines = batch,
+            SourceEvent::Idle => thread::sleep(Duration::from_millis(5)),
+            SourceEvent::Disconnected(e) => {
+                eprintln!(
+                    "{color_yellow}⚠️ Device connection lost ({color_red}{}{color_yellow}). Attempting to reconnect...{color_reset}",
+                    e
+                );
+                if let Some(ref mut writer) = log_writer {
+                    writeln!(
+                        writer,
+                        "ERROR [{}]: Connection lost: {}",
+                        get_timestamp(),
+                        e
+                    )?;
+                    writer.flush()?;
+                }
+            }
+            SourceEvent::Eof => {
+                // Input that does not end in a newline still counts as a line
+                lines.push(std::mem::take(&mut rx_buf));
+                finished = true;
+            }
+        }
+
+        for line in lines {
+            let trimmed = line.trim_end();
+            if trimmed.is_empty() {
+                continue;
+            }
+
+            let (line, readings) = match script {
+                Some(ref mut script) => {
+                    let output = script.on_line(trimmed);
+                    for msg in &output.logs {
+                        eprintln!("{color_bright_black}[script] {}{color_reset}", msg);
+                    }
+                    if let Some(msg) =
+                        crate::script::apply_effects(&output, source.as_mut(), &mut log_writer)
+                    {
+                        eprintln!("{color_red}🚨 {}{color_reset}", msg);
+                    }
+                    (output.line, output.readings)
+                }
+                None => (
+                    Some(trimmed.to_string()),
+                    crate::parser::parse_sensor_data(trimmed),
+                ),
+            };
+            if line.is_some() {
+                line_count += 1;
+            }
+
+            if let Some(ref line) = line
+                && let Some(ref mut writer) = log_writer
+            {
+                writeln!(writer, "RX [{}]: {}", get_timestamp(), line)?;
+                writer.flush()?;
+            }
+
+            if let Some(ref mut streamer) = csv_streamer {
+                streamer.write_row(&readings)?;
+            }
+
+            // A line the script dropped can still have emitted readings
+            let written = match (&mut output, &line) {
+                (Some(writer), _) if !readings.is_empty() => writer.write(&readings),
+                (Some(_), _) | (None, None) => Ok(()),
+                (None, Some(line)) if config.verbose => {
+                    writeln!(io::stdout(), "[{}] {}", get_timestamp(), line)
+                }
+                (None, Some(line)) => writeln!(io::stdout(), "{}", line),
+            };
+            match written {
+                Ok(()) => {}
+                // The reader went away (`| head`); that is a normal way to stop
+                Err(ref e) if e.kind() == io::ErrorKind::BrokenPipe => {
+                    break 'run HeadlessExit::Done;
+                }
+                Err(e) => return Err(e.into()),
+            }
+
+            if let Some(ref pattern) = config.until
+                && let Some(ref line) = line
+                && pattern.is_match(line)
+            {
+                eprintln!(
+                    "{color_green}✅ Matched /{}/ after {} lines ({:.1}s){color_reset}",
+                    pattern,
+                    line_count,
+                    started.elapsed().as_secs_f64()
+                );
+                break 'run HeadlessExit::Done;
+            }
+
+            if config.max_lines.is_some_and(|max| line_count >= max) {
+                eprintln!("{color_yellow}📄 Reached {} lines{color_reset}", line_count);
+                break 'run limit_outcome(&config, ever_connected);
+            }
+        }
+
+        if finished {
+            eprintln!(
+                "{color_yellow}Input finished after {} lines{color_reset}",
+                line_count
+            );
+            break limit_outcome(&config, ever_connected);
+        }
+    };
+
+    if let Some(stats) = source.frame_stats() {
+        eprintln!(
+            "{color_blue}{}: {} frames, {} frame errors, {} decode errors{color_reset}",
+            stats.framing, stats.frames, stats.frame_errors, stats.decode_errors
+        );
+    }
+
+    if outcome == HeadlessExit::UntilNotSeen
+        && let Some(ref pattern) = config.until
+    {
+        eprintln!("{color_red}❌ /{}/ was never seen{color_reset}", pattern);
+    } else if outcome == HeadlessExit::NeverConnected {
+        eprintln!(
+            "{color_red}❌ Could not connect to {}{color_reset}",
+            source.describe()
+        );
+    }
+
+    Ok(outcome)
+}
+
+/// Outcome when the run ends without `--until` having matched.
+fn limit_outcome(config: &MergedConfig, ever_connected: bool) -> HeadlessExit {
+    if !ever_connected {
+        HeadlessExit::NeverConnected
+    } else if config.until.is_some() {
+        HeadlessExit::UntilNotSeen
+    } else {
+        HeadlessExit::Done
+    }
+}
@@ -1,38 +1,62 @@
 use clap::Parser;
 use inline_colorization::*;
+use std::process::ExitCode;
 
+mod backtrace;
 mod config;
+mod defmt_stream;
 mod dual_ports;
 mod export;
+mod framing;
+mod headless;
 mod monitor;
 mod parser;
+mod pipe;
 mod plotter;
 mod port_finder;
 mod replay;
+mod rfc2217;
 mod rtt_reader;
+mod scenario;
+mod schema;
+mod script;
 mod serial;
+mod serve;
+mod source;
+mod tcp;
+mod test_runner;
+#[cfg(target_os = "linux")]
+mod virtual_port;
 
 #[cfg(feature = "ble")]
 mod ble;
+#[cfg(feature = "ble")]
+mod ble_adv;
+#[cfg(feature = "ble")]
+mod bthome;
+#[cfg(feature = "ble")]
+mod gatt;
 
 use config::{
     Args, generate_default_config, load_config, merge_config_and_args, print_completions,
 };
+use source::{DataSource, SerialOpen, SimProfile};
+
+/// Startup banners go to stderr in headless runs so stdout only carries data.
+macro_rules! announce {
+    ($headless:expr, $($arg:tt)*) => {
+        if $headless {
+            eprintln!($($arg)*);
+        } else {
+            println!($($arg)*);
+        }
+    };
+}
 
 pub enum AppExitState {
     Quit,
-    SwitchToPlotter {
-        port: Option<Box<dyn serialport::SerialPort>>,
-        rtt_reader: Option<crate::rtt_reader::RttDefmtReader>,
-        #[cfg(feature = "ble")]
-        ble_rx: Option<std::sync::mpsc::Receiver<crate::ble::BleEvent>>,
-    },
-    SwitchToMonitor {
-        port: Option<Box<dyn serialport::SerialPort>>,
-        rtt_reader: Option<crate::rtt_reader::RttDefmtReader>,
-        #[cfg(feature = "ble")]
-        ble_rx: Option<std::sync::mpsc::Receiver<crate::ble::BleEvent>>,
-    },
+    SwitchToPlotter { source: Box<dyn DataSource> },
+    SwitchToMonitor { source: Box<dyn DataSource> },
 }
 
 fn list_available_ports() -> Result<(), Box<dyn std::error::Error>> {
@@ -61,49 +85,110 @@ fn list_available_ports() -> Result<(), Box<dyn std::error::Error>> {
     Ok(())
 }
 
-fn main() -> Result<(), Box<dyn std::error::Error>> {
-    let args = Args::parse();
+fn main() -> Result<ExitCode, Box<dyn std::error::Error>> {
+    let mut args = Args::parse();
+    let command = args.command.take();
 
     if let Some(shell) = args.completions {
         print_completions(shell);
-        return Ok(());
+        return Ok(ExitCode::SUCCESS);
     }
 
     if args.generate_config {
-        return generate_default_config(args.config_file);
+        generate_default_config(args.config_file)?;
+        return Ok(ExitCode::SUCCESS);
     }
 
     let config = load_config(args.config_file.clone())?;
     let merged = merge_config_and_args(config, args);
 
     if merged.list_ports {
-        return list_available_ports();
+        list_available_ports()?;
+        return Ok(ExitCode::SUCCESS);
+    }
+
+    #[cfg(feature = "ble")]
+    if merged.ble_list {
+        ble::list_gatt(&merged)?;
+        return Ok(ExitCode::SUCCESS);
     }
 
     // ── CHECK FOR DUAL PORT MODE FIRST ────────────────────────────────────────
     // Allow dual UI for standard serial and simulate modes. We disable it for
-    // Replay, RTT, and BLE as those use specialized single-stream setups.
+    // Replay, RTT, BLE and virtual devices as those use single-stream setups.
     if merged.replay_file.is_none()
         && !merged.rtt
         && !merged.ble
+        && !merged.virtual_port
+        && command.is_none()
         && let Some(ports) = &merged.port
         && ports.len() == 2
     {
         crate::dual_ports::run_dual_mode(merged.clone(), ports.clone())?;
-        return Ok(());
+        return Ok(ExitCode::SUCCESS);
     }
 
+    let headless = merged.headless;
+    let (sim_profile, serial_open) = if merged.plot {
+        (SimProfile::Imu, SerialOpen::PLOTTER)
+    } else {
+        (SimProfile::Environment, SerialOpen::MONITOR)
+    };
+
+    // Kept alive for the whole session; dropping it unplugs the device
+    #[cfg(target_os = "linux")]
+    let virtual_device = if merged.virtual_port {
+        let device = virtual_port::VirtualPort::create(
+            merged.virtual_script.as_deref(),
+            sim_profile,
+            merged.zephyr,
+        )?;
+        announce!(
+            headless,
+            "{color_magenta}Starting virtual device on {}....{color_reset}",
+            device.path()
+        );
+        Some(device)
+    } else {
+        None
+    };
+    #[cfg(target_os = "linux")]
+    let virtual_path = virtual_device.as_ref().map(|d| d.path());
+    #[cfg(not(target_os = "linux"))]
+    let virtual_path: Option<String> = if merged.virtual_port {
+        return Err("Virtual devices are only supported on Linux".into());
+    } else {
+        None
+    };
+
     let port_name = if merged.simulate || merged.replay_file.is_some() || merged.rtt || merged.ble {
         if merged.rtt {
-            println!("{color_magenta}Starting in RTT/DEFMT debug probe mode....{color_reset}");
+            announce!(
+                headless,
+                "{color_magenta}Starting in RTT/DEFMT debug probe mode....{color_reset}"
+            );
             "RTT_DEBUG_PROBE".to_string()
+        } else if merged.ble_adv {
+            announce!(
+                headless,
+                "{color_magenta}Starting in BLE advertisement scan mode....{color_reset}"
+            );
+            "BLE_ADVERTISEMENTS".to_string()
         } else if merged.ble {
-            println!("{color_magenta}Starting in BLE stream mode....{color_reset}");
+            announce!(
+                headless,
+                "{color_magenta}Starting in BLE stream mode....{color_reset}"
+            );
             "BLE_STREAM".to_string()
         } else {
-            println!("{color_magenta}Starting in SIMULATE/REPLAY mode....{color_reset}");
+            announce!(
+                headless,
+                "{color_magenta}Starting in SIMULATE/REPLAY mode....{color_reset}"
+            );
             "SIMULATE_PORT".to_string()
         }
+    } else if let Some(path) = virtual_path {
+        path
     } else {
         let first_port = merged
             .port
@@ -115,7 +200,8 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         if first_port.to_lowercase() == "auto" {
             match port_finder::find_usb_port()? {
                 Some(detected) => {
-                    println!(
+                    announce!(
+                        headless,
                         "{color_green} Auto-detected USB Port: {}{color_reset}",
                         detected
                     );
@@ -134,95 +220,75 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         }
     };
 
-    let mut is_plot_mode = merged.plot;
-    let mut active_port: Option<Box<dyn serialport::SerialPort>> = None;
-    let mut active_rtt: Option<crate::rtt_reader::RttDefmtReader> = None;
-
+    // The `--ble-adv` table view reads the scanner's device list directly
     #[cfg(feature = "ble")]
-    let mut active_ble_rx: Option<std::sync::mpsc::Receiver<crate::ble::BleEvent>> = None;
-
-    #[cfg(feature = "ble")]
-    let _ble_rt = if merged.ble {
-        let (tx, rx) = std::sync::mpsc::channel();
-        let rt = ble::start_ble_stream(tx).unwrap_or_else(|e| {
-            eprintln!("{color_red}BLE Error: {}{color_reset}", e);
-            std::process::exit(1);
-        });
-        active_ble_rx = Some(rx);
-        Some(rt)
+    let (mut active_source, adv_devices) = if merged.ble_adv {
+        let scanner = ble_adv::AdvSource::start(&merged)?;
+        let devices = scanner.devices();
+        (Box::new(scanner) as Box<dyn DataSource>, Some(devices))
     } else {
-        None
+        (
+            source::from_config(&merged, &port_name, sim_profile, serial_open)?,
+            None,
+        )
     };
+    #[cfg(not(feature = "ble"))]
+    let mut active_source = source::from_config(&merged, &port_name, sim_profile, serial_open)?;
+
+    if let Some(ref address) = merged.serve {
+        let served = serve::ServedSource::bind(active_source, address, merged.serve_write)
+            .map_err(|e| format!("Failed to listen on {}: {}", address, e))?;
+        announce!(
+            headless,
+            "{color_cyan}󰒍 Sharing {} on tcp://{} (client writes: {:?}){color_reset}",
+            port_name,
+            served.address(),
+            merged.serve_write
+        );
+        active_source = Box::new(served);
+    }
+    // Outermost, so `--serve` clients still get the raw byte stream
+    active_source = framing::wrap(&merged, active_source)?;
+    active_source = defmt_stream::wrap(&merged, active_source)?;
+
+    if let Some(config::Command::Test { script, junit }) = command {
+        return test_runner::run_test_script(&merged, active_source, &script, &junit);
+    }
+
+    if merged.headless {
+        let outcome = crate::headless::run_headless_mode(merged.clone(), active_source)?;
+        return Ok(ExitCode::from(outcome as u8));
+    }
+
+    let mut is_plot_mode = merged.plot;
 
     loop {
         let result = if is_plot_mode {
-            #[cfg(feature = "ble")]
-            let res = crate::plotter::run_plotter_mode(
-                merged.clone(),
-                port_name.clone(),
-                active_port,
-                active_rtt,
-                active_ble_rx,
-            );
-            #[cfg(not(feature = "ble"))]
-            let res = crate::plotter::run_plotter_mode(
-                merged.clone(),
-                port_name.clone(),
-                active_port,
-                active_rtt,
-            );
-            res
+            crate::plotter::run_plotter_mode(merged.clone(), active_source)?
         } else {
             #[cfg(feature = "ble")]
-            let res = crate::monitor::run_normal_mode(
-                merged.clone(),
-                port_name.clone(),
-                active_port,
-                active_rtt,
-                active_ble_rx,
-            );
+            let result = match adv_devices {
+                Some(ref devices) => {
+                    crate::ble_adv::run_table_mode(merged.clone(), active_source, devices)?
+                }
+                None => crate::monitor::run_normal_mode(merged.clone(), active_source)?,
+            };
             #[cfg(not(feature = "ble"))]
-            let res = crate::monitor::run_normal_mode(
-                merged.clone(),
-                port_name.clone(),
-                active_port,
-                active_rtt,
-            );
-            res
+            let result = crate::monitor::run_normal_mode(merged.clone(), active_source)?;
+            result
         };
 
         match result {
-            Ok(AppExitState::Quit) => break,
-            Ok(AppExitState::SwitchToPlotter {
-                port,
-                rtt_reader,
-                #[cfg(feature = "ble")]
-                ble_rx,
-            }) => {
+            AppExitState::Quit => break,
+            AppExitState::SwitchToPlotter { source } => {
                 is_plot_mode = true;
-                active_port = port;
-                active_rtt = rtt_reader;
-                #[cfg(feature = "ble")]
-                {
-                    active_ble_rx = ble_rx;
-                }
+                active_source = source;
             }
-            Ok(AppExitState::SwitchToMonitor {
-                port,
-                rtt_reader,
-                #[cfg(feature = "ble")]
-                ble_rx,
-            }) => {
+            AppExitState::SwitchToMonitor { source } => {
                 is_plot_mode = false;
-                active_port = port;
-                active_rtt = rtt_reader;
-                #[cfg(feature = "ble")]
-                {
-                    active_ble_rx = ble_rx;
-                }
+                active_source = source;
             }
-            Err(e) => return Err(e),
         }
     }
-    Ok(())
+    Ok(ExitCode::SUCCESS)
 }
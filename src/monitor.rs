@@ -1,11 +1,10 @@
 use crate::config::MergedConfig;
-use crate::rtt_reader::RttDefmtReader;
-use crate::serial::{
-    get_timestamp, parse_data_bits, parse_flow_control, parse_parity, parse_stop_bits,
-};
+use crate::script::{LineScript, ScriptOutput};
+use crate::serial::get_timestamp;
+use crate::source::{DataSource, OpenError, SourceEvent};
 use inline_colorization::*;
 use std::fs::OpenOptions;
-use std::io::{self, BufWriter, Read, Write};
+use std::io::{self, BufWriter, Write};
 use std::sync::mpsc;
 use std::thread;
 use std::time::Duration;
@@ -20,10 +19,26 @@ use pretty_hex::*;
 
 enum MonitorCommand {
     Repaint(u8),
+    /// Cycle which channel of a multi-channel source is shown.
+    NextChannel,
     SwitchMode,
     Quit,
 }
 
+/// Turns bare `\n` into `\r\n` so lines do not staircase in raw mode.
+/// `after_cr` carries whether the previous chunk ended in `\r`.
+fn with_crlf(text: &str, after_cr: &mut bool) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        if c == '\n' && !*after_cr {
+            out.push('\r');
+        }
+        out.push(c);
+        *after_cr = c == '\r';
+    }
+    out
+}
+
 fn strip_ansi(s: &str) -> String {
     let mut out = String::with_capacity(s.len());
 
@@ -52,47 +67,55 @@ fn strip_ansi(s: &str) -> String {
     out
 }
 
-#[cfg(feature = "ble")]
-macro_rules! poll_ctrl_rx_while_waiting {
-    ($ctrl_rx:expr, $running:expr, $ble_rx:expr) => {
-        let range = core::range::Range { start: 0, end: 20 };
-        for _ in range {
-            if let Ok(cmd) = $ctrl_rx.try_recv() {
-                match cmd {
-                    MonitorCommand::SwitchMode => {
-                        terminal::disable_raw_mode().ok();
-                        return Ok(crate::AppExitState::SwitchToPlotter {
-                            port: None,
-                            rtt_reader: None,
-                            ble_rx: $ble_rx.take(),
-                        });
-                    }
-                    MonitorCommand::Quit => {
-                        println!("\r\n{color_yellow}󰏃 Shutting down ComChan…{color_reset}");
-                        $running.store(false, std::sync::atomic::Ordering::SeqCst);
-                        return Ok(crate::AppExitState::Quit);
-                    }
-                    _ => {}
-                }
-            }
-            thread::sleep(Duration::from_millis(50));
+/// Prints one complete line in raw mode, with a timestamp when verbose.
+fn show_line(line: &str, verbose: bool) {
+    if verbose && !strip_ansi(line).trim().is_empty() {
+        print!("\r[{}] {}\r\n", get_timestamp(), line);
+    } else {
+        print!("\r{}\r\n", line);
+    }
+}
+
+/// Applies a `--script` hook's side effects and shows its notes and alert inline.
+fn apply_script_effects(
+    output: &ScriptOutput,
+    source: &mut Box<dyn DataSource>,
+    log_writer: &mut Option<BufWriter<std::fs::File>>,
+) {
+    for msg in &output.logs {
+        print!("\r{color_bright_black}[script] {}{color_reset}\r\n", msg);
+    }
+    if let Some(msg) = crate::script::apply_effects(output, source.as_mut(), log_writer) {
+        print!("\r{color_red}🚨 {}{color_reset}\x07\r\n", msg);
+    }
+}
+
+/// Prints and logs the symbolised addresses of an ESP panic line, if it is one.
+fn show_backtrace(
+    symbolizer: &crate::backtrace::Symbolizer,
+    line: &str,
+    visible: bool,
+    log_writer: &mut Option<BufWriter<std::fs::File>>,
+) {
+    for decoded in symbolizer.decode_line(line) {
+        if visible {
+            print!("\r{color_cyan}  ↳ {}{color_reset}\r\n", decoded);
         }
-    };
+        if let Some(writer) = log_writer {
+            writeln!(writer, "BACKTRACE [{}]: {}", get_timestamp(), decoded).ok();
+        }
+    }
 }
 
-#[cfg(not(feature = "ble"))]
 macro_rules! poll_ctrl_rx_while_waiting {
-    ($ctrl_rx:expr, $running:expr) => {
+    ($ctrl_rx:expr, $running:expr, $source:expr) => {
         let range = core::range::Range { start: 0, end: 20 };
         for _ in range {
             if let Ok(cmd) = $ctrl_rx.try_recv() {
                 match cmd {
                     MonitorCommand::SwitchMode => {
                         terminal::disable_raw_mode().ok();
-                        return Ok(crate::AppExitState::SwitchToPlotter {
-                            port: None,
-                            rtt_reader: None,
-                        });
+                        return Ok(crate::AppExitState::SwitchToPlotter { source: $source });
                     }
                     MonitorCommand::Quit => {
                         println!("\r\n{color_yellow}󰏃 Shutting down ComChan…{color_reset}");
@@ -109,28 +132,8 @@ macro_rules! poll_ctrl_rx_while_waiting {
 
 pub fn run_normal_mode(
     config: MergedConfig,
-    port_name: String,
-    passed_port: Option<Box<dyn serialport::SerialPort>>,
-    passed_rtt: Option<crate::rtt_reader::RttDefmtReader>,
-    #[cfg(feature = "ble")] mut active_ble_rx: Option<
-        std::sync::mpsc::Receiver<crate::ble::BleEvent>,
-    >,
+    mut source: Box<dyn DataSource>,
 ) -> Result<crate::AppExitState, Box<dyn std::error::Error>> {
-    let skip_serial =
-        config.simulate || config.replay_file.is_some() || config.rtt || port_name == "BLE_STREAM";
-
-    let serial_config = if skip_serial {
-        None
-    } else {
-        Some((
-            parse_data_bits(config.data_bits).map_err(|e| format!("Configuration error: {}", e))?,
-            parse_stop_bits(config.stop_bits).map_err(|e| format!("Configuration error: {}", e))?,
-            parse_parity(&config.parity).map_err(|e| format!("Configuration error: {}", e))?,
-            parse_flow_control(&config.flow_control)
-                .map_err(|e| format!("Configuration error: {}", e))?,
-        ))
-    };
-
     // 1. Setup logging ONCE
     let mut log_writer = if let Some(log_path) = &config.log_file {
         let file = OpenOptions::new()
@@ -151,13 +154,12 @@ pub fn run_normal_mode(
         None
     };
 
-    let mut session_replayer = if let Some(ref path) = config.replay_file {
-        Some(
-            crate::replay::SessionReplayer::new(path)
-                .map_err(|e| format!("Failed to open replay file '{}': {}", path, e))?,
-        )
-    } else {
-        None
+    let mut script = config.script.as_deref().map(LineScript::load).transpose()?;
+
+    // RTT firmware is not an ESP, so the ELF is only for panic output on other links
+    let symbolizer = match config.elf {
+        Some(ref elf) if !config.rtt => Some(crate::backtrace::Symbolizer::load(elf)?),
+        _ => None,
     };
 
     println!("{color_green} Listening… (Ctrl+C to exit, Ctrl+L to clear screen){color_reset}\n");
@@ -196,6 +198,9 @@ pub fn run_normal_mode(
                                 ctrl_tx.send(MonitorCommand::SwitchMode).ok();
                                 break;
                             }
+                            (KeyCode::Char('t'), KeyModifiers::CONTROL) => {
+                                ctrl_tx.send(MonitorCommand::NextChannel).ok();
+                            }
                             (KeyCode::Char('c'), KeyModifiers::CONTROL) => {
                                 ctrl_tx.send(MonitorCommand::Quit).ok();
                                 break;
@@ -314,74 +319,23 @@ pub fn run_normal_mode(
 
     let running = std::sync::Arc::new(std::sync::atomic::AtomicBool::new(true));
 
-    let mut buffer = [0u8; 1024];
     let mut last_sent: Option<String> = None;
     let mut line_acc = String::new();
     let mut rx_buf = String::new();
+    // With a script the screen shows processed lines, plus the unfinished tail
+    let mut partial_shown = false;
+    let mut echo_after_cr = false;
+    let mut frame_errors_shown = 0;
     let mut lines_discarded = 0;
     const DISCARD_COUNT: usize = 5;
-
-    let mut active_port = passed_port;
-    let mut active_rtt = passed_rtt;
+    // Channel of a multi-channel source (RTT) to show; `None` shows them all
+    let mut channel_filter: Option<usize> = None;
 
     // Connection & Reconnection
     while running.load(std::sync::atomic::Ordering::SeqCst) {
-        // Initialize RTT reader
-        let mut rtt_reader = if let Some(r) = active_rtt.take() {
-            Some(r)
-        } else if config.rtt {
-            let elf = config.elf.as_deref().unwrap_or("");
-
-            match RttDefmtReader::new(elf, config.chip.clone()) {
-                Ok(reader) => Some(reader),
-                Err(e) => {
-                    let err_msg = e.to_string();
-
-                    if err_msg.contains("No such file")
-                        || err_msg.contains("No defmt table")
-                        || err_msg.contains("ChipNotFound")
-                        || err_msg.contains("requires an ELF file")
-                    {
-                        return Err(e);
-                    }
-                    // Otherwise, treat as a transient hardware/connection error and wait
-                    print!("\r{color_yellow}⏳ Waiting for RTT target...{color_reset}\x1b[K");
-                    io::stdout().flush().ok();
-
-                    #[cfg(feature = "ble")]
-                    poll_ctrl_rx_while_waiting!(ctrl_rx, running, active_ble_rx);
-                    #[cfg(not(feature = "ble"))]
-                    poll_ctrl_rx_while_waiting!(ctrl_rx, running);
-
-                    continue;
-                }
-            }
-        } else {
-            None
-        };
-
-        // Serial Port
-        let mut port = if let Some(p) = active_port.take() {
-            Some(p)
-        } else if skip_serial {
-            None
-        } else {
-            // Match handles the error instead of returning it
-            let (data_bits, stop_bits, parity, flow_control) = serial_config.unwrap();
-            match serialport::new(&port_name, config.baud)
-                .timeout(Duration::from_millis(config.timeout_ms))
-                .data_bits(data_bits)
-                .stop_bits(stop_bits)
-                .parity(parity)
-                .flow_control(flow_control)
-                .open()
-            {
-                Ok(mut p) => {
-                    let _ = p.write_data_terminal_ready(false);
-                    thread::sleep(Duration::from_millis(config.reset_delay_ms));
-                    let _ = p.write_all(b"\r");
-                    let _ = p.flush();
-
+        if !source.is_open() {
+            match source.reconnect() {
+                Ok(()) => {
                     if config.zephyr {
                         let mut suppress = false;
                         if let Some(ref sent) = last_sent {
@@ -398,9 +352,16 @@ pub fn run_normal_mode(
                     }
 
                     println!(
-                        "\r\n{color_green}🔌 Connected to {} at {} baud{color_reset}",
-                        port_name, config.baud
+                        "\r\n{color_green}🔌 Connected to {}{color_reset}",
+                        source.describe()
                     );
+                    let channels = source.channels();
+                    if channels.len() > 1 {
+                        println!(
+                            "\r{color_blue}📺 Channels: {} (Ctrl+T to switch){color_reset}",
+                            channels.join(", ")
+                        );
+                    }
                     if config.verbose {
                         println!(
                             "\r{color_blue}⚙️  Config: {} data bits, {} stop bits, {} parity, {} flow control{color_reset}",
@@ -410,331 +371,305 @@ pub fn run_normal_mode(
                             println!("\r{color_blue} Logging to: {}{color_reset}", log_path);
                         }
                     }
-                    Some(p)
                 }
-                Err(_) => {
-                    // Retry connection every 1 second
+                Err(OpenError::Fatal(e)) => return Err(e),
+                Err(OpenError::Unavailable(e)) => {
+                    // Retry connection every 1 second; the reason carries
+                    // progress such as a BLE scan or connect
                     print!(
-                        "\r{color_yellow}⏳ Waiting for device on {}...{color_reset}\x1b[K",
-                        port_name
+                        "\r{color_yellow}⏳ Waiting for {}: {}{color_reset}\x1b[K",
+                        source.describe(),
+                        e.lines().next().unwrap_or_default()
                     );
                     io::stdout().flush().ok();
 
-                    #[cfg(feature = "ble")]
-                    poll_ctrl_rx_while_waiting!(ctrl_rx, running, active_ble_rx);
-                    #[cfg(not(feature = "ble"))]
-                    poll_ctrl_rx_while_waiting!(ctrl_rx, running);
+                    poll_ctrl_rx_while_waiting!(ctrl_rx, running, source);
 
                     continue;
                 }
             }
-        };
+        }
 
-        let mut is_connected = true;
         let mut hex_buf: Vec<u8> = Vec::new();
+        let channel_names = source.channels();
 
         // Read / Write Data
-        while running.load(std::sync::atomic::Ordering::SeqCst) && is_connected {
-            if config.simulate {
-                let t = std::time::SystemTime::now()
-                    .duration_since(std::time::UNIX_EPOCH)
-                    .unwrap()
-                    .as_secs_f64();
-                let sim_text = format!(
-                    "Temperature: {:2}, Humidity: {:.2}, Pressure: {:.2}\r\n",
-                    (t * 1.0).sin() * 50.0,
-                    (t * 0.8).cos() * 50.0,
-                    (t * 0.5).sin() * 50.0
-                );
-
-                if config.hex_mode || config.hex_pretty {
-                    let hex_out = format!("{:?}", sim_text.as_bytes().hex_dump());
-                    let raw_mode_safe_hex = hex_out.replace('\n', "\r\n");
-                    print!("\r\n{}\r\n", raw_mode_safe_hex);
-                    io::stdout().flush().ok();
-                } else {
-                    io::stdout().write_all(sim_text.as_bytes()).ok();
-                    io::stdout().flush().ok();
-                }
-
-                if let Some(ref mut streamer) = csv_streamer {
-                    let clean = strip_ansi(sim_text.trim());
-                    let readings = crate::parser::parse_sensor_data(&clean);
-                    let _ = streamer.write_row(&readings);
-                }
-
-                thread::sleep(Duration::from_millis(500));
-            }
-
-            if let Some(ref mut replayer) = session_replayer {
-                match replayer.next_payload() {
-                    crate::replay::ReplayEvent::Payload(payload) => {
-                        let text = format!("{}\r\n", payload);
-
-                        io::stdout().write_all(text.as_bytes()).ok();
-                        io::stdout().flush().ok();
-                    }
-                    crate::replay::ReplayEvent::Waiting => {}
-                    crate::replay::ReplayEvent::Eof => {
-                        println!("\n{color_yellow}Replay Finished.{color_reset}");
-                        running.store(false, std::sync::atomic::Ordering::SeqCst);
-                        break;
-                    }
-                }
-            }
-
-            if let Some(reader) = rtt_reader.as_mut() {
-                match reader.poll_logs() {
-                    Ok(logs) => {
-                        if logs.is_empty() {
-                            thread::sleep(Duration::from_millis(10));
+        while running.load(std::sync::atomic::Ordering::SeqCst) && source.is_open() {
+            let event = source.read();
+            let channel = match event {
+                SourceEvent::ChannelLines(channel, _) => Some(channel),
+                _ => None,
+            };
+            match event {
+                SourceEvent::Bytes(raw) => {
+                    if config.hex_mode || config.hex_pretty {
+                        let (should_print, data_to_print) = if config.hex_pretty {
+                            hex_buf.extend_from_slice(&raw);
+
+                            if hex_buf.contains(&b'\n') || hex_buf.len() >= 64 {
+                                let data = hex_buf.clone();
+                                hex_buf.clear();
+                                (true, data)
+                            } else {
+                                (false, Vec::new())
+                            }
                         } else {
-                            for line in logs {
-                                let trimmed = line.trim_end();
+                            (true, raw.clone())
+                        };
 
-                                if trimmed.is_empty() {
-                                    continue;
-                                }
+                        if should_print {
+                            let hex_out = format!("{:?}", data_to_print.hex_dump());
+                            let raw_mode_safe_hex = hex_out.replace('\n', "\r\n");
 
-                                if config.verbose {
-                                    print!("\r[{}] {}\r\n", get_timestamp(), trimmed);
-                                } else {
-                                    print!("\r{}\r\n", trimmed);
-                                }
-                                io::stdout().flush().ok();
-
-                                if let Some(ref mut writer) = log_writer {
-                                    writeln!(writer, "RX [{}]: {}", get_timestamp(), trimmed).ok();
-                                    let _ = writer.flush();
-                                }
+                            print!("\r\n{}\r\n", raw_mode_safe_hex);
+                            io::stdout().flush().ok();
 
-                                if let Some(ref mut streamer) = csv_streamer {
-                                    let readings = crate::parser::parse_sensor_data(trimmed);
-                                    let _ = streamer.write_row(&readings);
-                                }
+                            if let Some(ref mut writer) = log_writer {
+                                writeln!(writer, "RX HEX [{}]:\n{}", get_timestamp(), hex_out).ok();
+                                let _ = writer.flush();
                             }
                         }
+                        continue;
                     }
 
-                    Err(e) => {
-                        eprintln!(
-                            "\r\n{color_yellow}⚠️ RTT connection lost ({color_red}{}{color_yellow}). Attempting to reconnect...{color_reset}",
-                            e
-                        );
-
-                        if let Some(ref mut writer) = log_writer {
-                            writeln!(
-                                writer,
-                                "ERROR [{}]: RTT connection lost: {}",
-                                get_timestamp(),
-                                e
-                            )
-                            .ok();
-                            let _ = writer.flush();
+                    let text = String::from_utf8_lossy(&raw);
+                    // Serial devices are echoed byte for byte
+                    let echo = (!source.is_serial()).then(|| with_crlf(&text, &mut echo_after_cr));
+
+                    line_acc.push_str(&text);
+
+                    // ── Verbose timestamp prefix ─────────────────────────────────
+                    if script.is_some() {
+                        // Printed line by line below, once the script has run
+                    } else if config.verbose {
+                        let mut remaining = echo.as_deref().unwrap_or(&text);
+                        while let Some(pos) = remaining.find('\n') {
+                            let chunk = &remaining[..=pos];
+                            let clean = strip_ansi(chunk);
+                            if !clean.trim().is_empty() {
+                                print!("[{}] {}", get_timestamp(), chunk);
+                            } else {
+                                print!("{}", chunk);
+                            }
+                            remaining = &remaining[pos + 1..];
+                        }
+                        if !remaining.is_empty() {
+                            print!("{}", remaining);
                         }
-                        is_connected = false;
+                    } else {
+                        let bytes = echo.as_ref().map_or(raw.as_slice(), |e| e.as_bytes());
+                        io::stdout().write_all(bytes).ok();
                     }
-                }
-            }
-
-            #[cfg(feature = "ble")]
-            {
-                if let Some(rx) = active_ble_rx.as_ref() {
-                    while let Ok(event) = rx.try_recv() {
-                        match event {
-                            crate::ble::BleEvent::Disconnected => {
-                                eprintln!(
-                                    "\r\n{color_red}❌ BLE Connection Lost.{color_reset}\r\n"
-                                );
-                                is_connected = false;
-                                break;
-                            }
-                            crate::ble::BleEvent::Payload(text) => {
-                                rx_buf.push_str(&text);
-
-                                while let Some(pos) = rx_buf.find('\n') {
-                                    let full_line = rx_buf.drain(..=pos).collect::<String>();
-                                    let clean = strip_ansi(&full_line);
-                                    let trimmed = clean.trim_end();
+                    io::stdout().flush().ok();
 
-                                    if trimmed.is_empty() {
-                                        continue;
-                                    }
+                    // ── Logging & CSV streaming ───────────────────────────────────────────────────
+                    rx_buf.push_str(&text);
 
-                                    if lines_discarded < DISCARD_COUNT {
-                                        lines_discarded += 1;
-                                        continue;
-                                    }
+                    while let Some(pos) = rx_buf.find('\n') {
+                        let full_line = rx_buf.drain(..=pos).collect::<String>();
+                        let clean = strip_ansi(&full_line);
+                        let trimmed = clean.trim_end();
 
-                                    if config.verbose {
-                                        print!("\r[{}] {}\r\n", get_timestamp(), trimmed);
-                                    } else {
-                                        print!("\r{}\r\n", trimmed);
-                                    }
-                                    io::stdout().flush().ok();
+                        if partial_shown {
+                            print!("\r\x1b[K");
+                            partial_shown = false;
+                        }
 
-                                    if let Some(ref mut writer) = log_writer {
-                                        writeln!(writer, "RX [{}]: {}", get_timestamp(), trimmed)
-                                            .ok();
-                                        let _ = writer.flush();
-                                    }
+                        // Before the boot-noise discard, so a crash loop still decodes
+                        if let Some(ref symbolizer) = symbolizer {
+                            show_backtrace(symbolizer, trimmed, true, &mut log_writer);
+                        }
 
-                                    if let Some(ref mut streamer) = csv_streamer {
-                                        let readings = crate::parser::parse_sensor_data(trimmed);
-                                        let _ = streamer.write_row(&readings);
-                                    }
-                                }
+                        if trimmed.is_empty() || lines_discarded < DISCARD_COUNT {
+                            if !trimmed.is_empty() {
+                                lines_discarded += 1;
+                            }
+                            if script.is_some() {
+                                show_line(full_line.trim_end(), config.verbose);
                             }
+                            continue;
                         }
-                    }
-                }
-            }
-
-            if let Some(p) = port.as_mut() {
-                match p.read(&mut buffer) {
-                    Ok(n) if n > 0 => {
-                        let raw = &buffer[..n];
-
-                        if config.hex_mode || config.hex_pretty {
-                            let (should_print, data_to_print) = if config.hex_pretty {
-                                hex_buf.extend_from_slice(raw);
-
-                                if hex_buf.contains(&b'\n') || hex_buf.len() >= 64 {
-                                    let data = hex_buf.clone();
-                                    hex_buf.clear();
-                                    (true, data)
-                                } else {
-                                    (false, Vec::new())
-                                }
-                            } else {
-                                (true, raw.to_vec())
-                            };
-
-                            if should_print {
-                                let hex_out = format!("{:?}", data_to_print.hex_dump());
-                                let raw_mode_safe_hex = hex_out.replace('\n', "\r\n");
-
-                                print!("\r\n{}\r\n", raw_mode_safe_hex);
-                                io::stdout().flush().ok();
 
-                                if let Some(ref mut writer) = log_writer {
-                                    writeln!(writer, "RX HEX [{}]:\n{}", get_timestamp(), hex_out)
-                                        .ok();
-                                    let _ = writer.flush();
+                        let (line, readings) = match script {
+                            Some(ref mut script) => {
+                                let output = script.on_line(trimmed);
+                                apply_script_effects(&output, &mut source, &mut log_writer);
+                                // Unchanged lines keep the device's own colours on screen
+                                match output.line {
+                                    Some(ref line) if line == trimmed => {
+                                        show_line(full_line.trim_end(), config.verbose)
+                                    }
+                                    Some(ref line) => show_line(line, config.verbose),
+                                    None => {}
                                 }
+                                (output.line, output.readings)
                             }
-                            continue;
+                            None => (
+                                Some(trimmed.to_string()),
+                                crate::parser::parse_sensor_data(trimmed),
+                            ),
+                        };
+
+                        if let Some(ref line) = line
+                            && let Some(ref mut writer) = log_writer
+                        {
+                            writeln!(writer, "RX [{}]: {}", get_timestamp(), line).ok();
+                            let _ = writer.flush();
                         }
 
-                        let text = String::from_utf8_lossy(raw);
+                        if let Some(ref mut streamer) = csv_streamer {
+                            let _ = streamer.write_row(&readings);
+                        }
+                    }
 
-                        line_acc.push_str(&text);
+                    if script.is_some() && !rx_buf.is_empty() {
+                        // Prompts arrive without a newline, so show the tail as-is for now
+                        print!("\r\x1b[K{}", rx_buf.trim_end_matches(['\r', '\n']));
+                        partial_shown = true;
+                    }
+                    io::stdout().flush().ok();
 
-                        // ── Verbose timestamp prefix ─────────────────────────────────
-                        if config.verbose {
-                            let mut remaining = text.as_ref();
-                            while let Some(pos) = remaining.find('\n') {
-                                let chunk = &remaining[..=pos];
-                                let clean = strip_ansi(chunk);
-                                if !clean.trim().is_empty() {
-                                    print!("[{}] {}", get_timestamp(), chunk);
-                                } else {
-                                    print!("{}", chunk);
-                                }
-                                remaining = &remaining[pos + 1..];
-                            }
-                            if !remaining.is_empty() {
-                                print!("{}", remaining);
-                            }
-                        } else {
-                            io::stdout().write_all(raw).ok();
+                    if line_acc.contains('\n') {
+                        line_acc.clear();
+                    }
+                }
+                SourceEvent::Lines(lines) | SourceEvent::ChannelLines(_, lines) => {
+                    let visible = channel_filter.is_none() || channel_filter == channel;
+                    // Tag lines with their channel while several are interleaved
+                    let tag = match channel {
+                        Some(channel) if channel_names.len() > 1 && channel_filter.is_none() => {
+                            format!(
+                                "{color_bright_black}[{}]{color_reset} ",
+                                channel_names[channel]
+                            )
                         }
-                        io::stdout().flush().ok();
+                        _ => String::new(),
+                    };
 
-                        // ── Logging & CSV streaming ───────────────────────────────────────────────────
-                        rx_buf.push_str(&text);
+                    for line in lines {
+                        let trimmed = line.trim_end();
 
-                        while let Some(pos) = rx_buf.find('\n') {
-                            let full_line = rx_buf.drain(..=pos).collect::<String>();
-                            let clean = strip_ansi(&full_line);
-                            let trimmed = clean.trim_end();
+                        if trimmed.is_empty() {
+                            continue;
+                        }
 
-                            if trimmed.is_empty() {
-                                continue;
+                        let (line, readings) = match script {
+                            Some(ref mut script) => {
+                                let output = script.on_line(trimmed);
+                                apply_script_effects(&output, &mut source, &mut log_writer);
+                                (output.line, output.readings)
                             }
-
-                            if lines_discarded < DISCARD_COUNT {
-                                lines_discarded += 1;
-                                continue;
+                            None => (
+                                Some(trimmed.to_string()),
+                                crate::parser::parse_sensor_data(trimmed),
+                            ),
+                        };
+
+                        if let Some(ref line) = line {
+                            if visible {
+                                show_line(&format!("{}{}", tag, line), config.verbose);
+                                io::stdout().flush().ok();
                             }
 
                             if let Some(ref mut writer) = log_writer {
-                                writeln!(writer, "RX [{}]: {}", get_timestamp(), trimmed).ok();
+                                writeln!(writer, "RX [{}]: {}", get_timestamp(), line).ok();
                                 let _ = writer.flush();
                             }
-
-                            if let Some(ref mut streamer) = csv_streamer {
-                                let readings = crate::parser::parse_sensor_data(trimmed);
-                                let _ = streamer.write_row(&readings);
-                            }
                         }
-
-                        if line_acc.contains('\n') {
-                            line_acc.clear();
+                        if let Some(ref symbolizer) = symbolizer {
+                            show_backtrace(symbolizer, trimmed, visible, &mut log_writer);
                         }
-                    }
-                    Ok(_) => {}
-                    Err(ref e) if e.kind() == io::ErrorKind::TimedOut => {}
-                    Err(e) => {
-                        // Read Error -> Trigger Reconnection
-                        eprintln!(
-                            "\r\n{color_yellow}⚠️ Device connection lost ({color_red}{}{color_yellow}). Attempting to reconnect...{color_reset}",
-                            e
-                        );
 
-                        if let Some(ref mut writer) = log_writer {
-                            writeln!(
-                                writer,
-                                "ERROR [{}]: Connection lost: {}",
-                                get_timestamp(),
-                                e
-                            )
-                            .ok();
-                            let _ = writer.flush();
+                        if let Some(ref mut streamer) = csv_streamer {
+                            let _ = streamer.write_row(&readings);
                         }
+                    }
+                }
+                SourceEvent::Idle => {}
+                SourceEvent::Disconnected(e) => {
+                    // Read Error -> Trigger Reconnection
+                    eprintln!(
+                        "\r\n{color_yellow}⚠️ Device connection lost ({color_red}{}{color_yellow}). Attempting to reconnect...{color_reset}",
+                        e
+                    );
 
-                        is_connected = false;
+                    if let Some(ref mut writer) = log_writer {
+                        writeln!(
+                            writer,
+                            "ERROR [{}]: Connection lost: {}",
+                            get_timestamp(),
+                            e
+                        )
+                        .ok();
+                        let _ = writer.flush();
                     }
                 }
+                SourceEvent::Eof => {
+                    println!(
+                        "\n{color_yellow}Input finished ({}).{color_reset}",
+                        source.describe()
+                    );
+                    running.store(false, std::sync::atomic::Ordering::SeqCst);
+                    break;
+                }
+            }
+
+            // ── Rejected frames (--framing, defmt) ──────────────────────────────
+            if config.verbose
+                && let Some(stats) = source.frame_stats()
+                && stats.frame_errors + stats.decode_errors > frame_errors_shown
+            {
+                frame_errors_shown = stats.frame_errors + stats.decode_errors;
+                print!(
+                    "\r{color_yellow}⚠ {} frame rejected: {} ({} frame / {} decode errors){color_reset}\r\n",
+                    stats.framing,
+                    stats.last_error.unwrap_or("unknown"),
+                    stats.frame_errors,
+                    stats.decode_errors
+                );
+                io::stdout().flush().ok();
             }
 
             // Write user input
             if let Ok(input) = input_rx.try_recv() {
-                let clean = input.trim_end();
+                let mut clean = input.trim_end().to_string();
+                if let Some(ref mut script) = script
+                    && !clean.is_empty()
+                {
+                    let output = script.on_send(&clean);
+                    apply_script_effects(&output, &mut source, &mut log_writer);
+                    if let Some(ref mut streamer) = csv_streamer
+                        && !output.readings.is_empty()
+                    {
+                        let _ = streamer.write_row(&output.readings);
+                    }
+                    clean = output.line.unwrap_or_default();
+                }
                 if !clean.is_empty() {
                     let message = format!("{}\r", clean);
 
-                    if let Some(p) = port.as_mut() {
-                        if let Err(e) = p.write_all(message.as_bytes()) {
-                            // Write Error -> Trigger Reconnection
-                            eprintln!("\r\n{color_red}❌ Write error: {e}{color_reset}");
-                            if let Some(ref mut writer) = log_writer {
-                                writeln!(writer, "ERROR [{}]: Write error: {}", get_timestamp(), e)
-                                    .ok();
-                                let _ = writer.flush();
-                            }
-                            is_connected = false;
+                    if let Err(e) = source.write(message.as_bytes()) {
+                        // Nothing to reconnect for; a full RTT buffer just drops the line
+                        if matches!(
+                            e.kind(),
+                            io::ErrorKind::Unsupported | io::ErrorKind::TimedOut
+                        ) {
+                            eprintln!("\r\n{color_yellow}{e}{color_reset}");
                             continue;
                         }
-                        p.flush().ok();
-                    } else if config.rtt {
-                        eprintln!(
-                            "\r\n{color_yellow}Sending Input over RTT is not supported{color_reset}"
-                        );
+
+                        // Write Error -> Trigger Reconnection
+                        eprintln!("\r\n{color_red}❌ Write error: {e}{color_reset}");
+                        if let Some(ref mut writer) = log_writer {
+                            writeln!(writer, "ERROR [{}]: Write error: {}", get_timestamp(), e)
+                                .ok();
+                            let _ = writer.flush();
+                        }
+                        source.close();
                         continue;
                     }
 
-                    last_sent = Some(clean.to_string());
+                    last_sent = Some(clean.clone());
                     line_acc.clear();
 
                     if config.verbose {
@@ -754,19 +689,25 @@ pub fn run_normal_mode(
             if let Ok(cmd) = ctrl_rx.try_recv() {
                 match cmd {
                     MonitorCommand::Repaint(byte) => {
-                        if let Some(p) = port.as_mut() {
-                            let _ = p.write_all(&[byte]);
-                            let _ = p.flush();
-                        }
+                        let _ = source.write(&[byte]);
+                    }
+                    MonitorCommand::NextChannel if channel_names.len() > 1 => {
+                        channel_filter = match channel_filter {
+                            None => Some(0),
+                            Some(i) if i + 1 < channel_names.len() => Some(i + 1),
+                            Some(_) => None,
+                        };
+                        let shown = match channel_filter {
+                            Some(i) => channel_names[i].as_str(),
+                            None => "all channels",
+                        };
+                        print!("\r{color_blue}📺 Showing {}{color_reset}\r\n", shown);
+                        io::stdout().flush().ok();
                     }
+                    MonitorCommand::NextChannel => {}
                     MonitorCommand::SwitchMode => {
                         terminal::disable_raw_mode().ok();
-                        return Ok(crate::AppExitState::SwitchToPlotter {
-                            port,
-                            rtt_reader,
-                            #[cfg(feature = "ble")]
-                            ble_rx: active_ble_rx,
-                        });
+                        return Ok(crate::AppExitState::SwitchToPlotter { source });
                     }
                     MonitorCommand::Quit => {
                         println!("\r\n{color_yellow}󰏃 Shutting down ComChan…{color_reset}");
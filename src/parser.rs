@@ -146,6 +146,7 @@ pub fn parse_sensor_data(line: &str) -> Vec<(String, f64)> {
     let mut working_line = clean_line.trim();
 
     // ── NEW: Strip defmt/RTOS timestamps before any parsing happens ──
+    working_line = strip_defmt_location(working_line);
     working_line = strip_log_prefixes(working_line);
 
     // 2. Preprocess and strip metadata if it originates from a Zephyr logger
@@ -204,6 +205,19 @@ fn strip_ansi(s: &str) -> String {
     clean_str
 }
 
+/// Removes the `└─ module::path file.rs:123` suffix added by `--defmt-location`,
+/// so the line number is not mistaken for a reading.
+fn strip_defmt_location(line: &str) -> &str {
+    if let Some((message, location)) = line.rsplit_once(" └─ ")
+        && let Some((_, line_no)) = location.rsplit_once(':')
+        && !line_no.is_empty()
+        && line_no.chars().all(|c| c.is_ascii_digit())
+    {
+        return message.trim_end();
+    }
+    line
+}
+
 /// Preprocessor that strips Zephyr log headers, returning the raw payload slice.
 fn strip_zephyr_headers(line: &str) -> &str {
     let is_zephyr_log = line.contains("<inf>")
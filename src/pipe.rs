@@ -0,0 +1,122 @@
+use crate::config::PipeFormat;
+use crate::export::CsvStreamer;
+use crate::serial::get_timestamp;
+use crate::source::{DataSource, OpenError, SourceEvent};
+use std::io::{self, BufWriter, Read, Write};
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::thread;
+
+/// `--port -`: the port name that selects [`StdinSource`].
+pub const STDIN_PORT: &str = "-";
+
+/// Reads the byte stream from standard input, e.g. `ssh board cat /dev/ttyACM0 | comchan -p -`.
+///
+/// Stdin is read on a helper thread so polling stays non-blocking. The
+/// interactive views still get keystrokes because crossterm falls back to
+/// `/dev/tty` when stdin is not a terminal.
+pub struct StdinSource {
+    rx: Option<Receiver<Vec<u8>>>,
+}
+
+impl StdinSource {
+    pub fn new() -> Self {
+        Self { rx: None }
+    }
+}
+
+impl DataSource for StdinSource {
+    fn open(&mut self) -> Result<(), OpenError> {
+        // Stdin can only be consumed once; re-opening after EOF keeps reporting EOF
+        if self.rx.is_none() {
+            let (tx, rx) = mpsc::channel();
+            thread::spawn(move || {
+                let mut stdin = io::stdin().lock();
+                let mut buffer = [0u8; 4096];
+                loop {
+                    match stdin.read(&mut buffer) {
+                        Ok(0) => break,
+                        Ok(n) => {
+                            if tx.send(buffer[..n].to_vec()).is_err() {
+                                break;
+                            }
+                        }
+                        Err(ref e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                        Err(_) => break,
+                    }
+                }
+            });
+            self.rx = Some(rx);
+        }
+        Ok(())
+    }
+
+    fn is_open(&self) -> bool {
+        self.rx.is_some()
+    }
+
+    fn close(&mut self) {}
+
+    fn read(&mut self) -> SourceEvent {
+        let Some(rx) = self.rx.as_ref() else {
+            return SourceEvent::Idle;
+        };
+
+        match rx.try_recv() {
+            Ok(mut data) => {
+                while let Ok(more) = rx.try_recv() {
+                    data.extend_from_slice(&more);
+                }
+                SourceEvent::Bytes(data)
+            }
+            Err(TryRecvError::Empty) => SourceEvent::Idle,
+            Err(TryRecvError::Disconnected) => SourceEvent::Eof,
+        }
+    }
+
+    fn write(&mut self, _data: &[u8]) -> io::Result<()> {
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "Sending Input to stdin is not supported",
+        ))
+    }
+
+    fn describe(&self) -> String {
+        "stdin".to_string()
+    }
+}
+
+/// Writes each parsed reading to stdout as CSV rows or JSON lines (`--pipe`).
+pub enum ReadingWriter {
+    Csv(CsvStreamer),
+    Json(Box<dyn Write>),
+}
+
+impl ReadingWriter {
+    pub fn stdout(format: PipeFormat) -> Self {
+        let stdout: Box<dyn Write> = Box::new(BufWriter::new(io::stdout()));
+        match format {
+            PipeFormat::Csv => ReadingWriter::Csv(CsvStreamer::from_writer(stdout)),
+            PipeFormat::Json => ReadingWriter::Json(stdout),
+        }
+    }
+
+    pub fn write(&mut self, readings: &[(String, f64)]) -> io::Result<()> {
+        match self {
+            ReadingWriter::Csv(streamer) => streamer.write_row(readings),
+            ReadingWriter::Json(out) => {
+                // Built by hand so keys keep the order the device sent them in
+                write!(out, "{{\"timestamp\":\"{}\"", get_timestamp())?;
+                for (name, value) in readings {
+                    write!(
+                        out,
+                        ",{}:{}",
+                        serde_json::Value::from(name.as_str()),
+                        serde_json::Value::from(*value)
+                    )?;
+                }
+                writeln!(out, "}}")?;
+                out.flush()
+            }
+        }
+    }
+}
@@ -1,9 +1,8 @@
 use crate::config::MergedConfig;
 use crate::parser::{SensorData, get_color_for_index, parse_sensor_data};
-use crate::rtt_reader::RttDefmtReader;
-use crate::serial::{
-    get_timestamp, parse_data_bits, parse_flow_control, parse_parity, parse_stop_bits,
-};
+use crate::script::LineScript;
+use crate::serial::get_timestamp;
+use crate::source::{DataSource, OpenError, SourceEvent};
 use crossterm::{
     event::{self, KeyCode, KeyModifiers},
     execute,
@@ -22,7 +21,7 @@ use ratatui::{
 };
 use std::collections::HashMap;
 use std::fs::OpenOptions;
-use std::io::{self, BufWriter, Read, Write};
+use std::io::{self, BufWriter, Write};
 use std::time::{Duration, Instant};
 
 use ratatui_wireframe::WireframeWidget;
@@ -126,6 +125,10 @@ struct PlotterState {
 
     show_help: bool,
     sensor_scroll: usize,
+
+    /// Channels of a multi-channel source (RTT) and the one being plotted
+    channel_names: Vec<String>,
+    channel_filter: Option<usize>,
 }
 
 const DISCARD_FIRST_LINES: usize = 3;
@@ -216,6 +219,9 @@ impl PlotterState {
 
             show_help: false,
             sensor_scroll: 0,
+
+            channel_names: Vec::new(),
+            channel_filter: None,
         }
     }
 
@@ -229,19 +235,27 @@ impl PlotterState {
         self.sensors.get_mut(name).unwrap()
     }
 
-    fn ingest_line(&mut self, line: &str, max_points: usize) {
-        let clean = line.trim();
-
+    /// Counts off the first few (often partial) lines after connecting.
+    fn accept_line(&mut self) -> bool {
         if self.lines_discarded < DISCARD_FIRST_LINES {
             self.lines_discarded += 1;
-            return;
+            return false;
+        }
+        true
+    }
+
+    fn ingest_line(&mut self, line: &str, max_points: usize, plot: bool) {
+        if self.accept_line() {
+            self.ingest_readings(parse_sensor_data(line.trim()), max_points, plot);
         }
+    }
 
+    /// Streams readings to the CSV and, when `plot` is set, onto the chart.
+    fn ingest_readings(&mut self, readings: Vec<(String, f64)>, max_points: usize, plot: bool) {
         if self.paused {
             return;
         }
 
-        let readings = parse_sensor_data(clean);
         if readings.is_empty() {
             return;
         }
@@ -249,6 +263,9 @@ impl PlotterState {
         if let Some(streamer) = &mut self.csv_streamer {
             let _ = streamer.write_row(&readings);
         }
+        if !plot {
+            return;
+        }
 
         for (name, value) in readings {
             let x = self.x;
@@ -321,6 +338,11 @@ impl PlotterState {
         }
     }
 
+    /// Whether lines from `channel` are plotted under the current channel tab.
+    fn shows_channel(&self, channel: Option<usize>) -> bool {
+        self.channel_filter.is_none() || self.channel_filter == channel
+    }
+
     fn uptime_str(&self) -> String {
         let secs = self.start_time.elapsed().as_secs();
         format!(
@@ -334,49 +356,62 @@ impl PlotterState {
 
 // ── Main entry point ──────────────────────────────────────────────────────────
 
+/// Logs and plots one received line, passing it through the `--script` hook
+/// first when there is one. Script alerts take the status bar. Lines from
+/// a channel other than the selected one are logged but not plotted.
+fn receive_line(
+    state: &mut PlotterState,
+    script: Option<&mut LineScript>,
+    channel: Option<usize>,
+    line: &str,
+    max_points: usize,
+    source: &mut Box<dyn DataSource>,
+    log_writer: &mut Option<BufWriter<std::fs::File>>,
+) {
+    let Some(script) = script else {
+        if let Some(writer) = log_writer {
+            let _ = writeln!(writer, "RX [{}]: {}", get_timestamp(), line.trim_end());
+            let _ = writer.flush();
+        }
+        let plot = state.shows_channel(channel);
+        state.ingest_line(line, max_points, plot);
+        return;
+    };
+
+    if !state.accept_line() {
+        return;
+    }
+
+    let output = script.on_line(line.trim());
+    if let Some(msg) = crate::script::apply_effects(&output, source.as_mut(), log_writer) {
+        state.last_error = Some(format!("🚨 {}", msg));
+    }
+    if let Some(ref kept) = output.line
+        && let Some(writer) = log_writer
+    {
+        let _ = writeln!(writer, "RX [{}]: {}", get_timestamp(), kept);
+        let _ = writer.flush();
+    }
+
+    let plot = state.shows_channel(channel);
+    state.ingest_readings(output.readings, max_points, plot);
+}
+
 pub fn run_plotter_mode(
     config: MergedConfig,
-    port_name: String,
-    passed_port: Option<Box<dyn serialport::SerialPort>>,
-    passed_rtt: Option<crate::rtt_reader::RttDefmtReader>,
-    #[cfg(feature = "ble")] active_ble_rx: Option<std::sync::mpsc::Receiver<crate::ble::BleEvent>>,
+    mut source: Box<dyn DataSource>,
 ) -> Result<crate::AppExitState, Box<dyn std::error::Error>> {
-    // The Fix: Prevent BLE from attempting to initialize a physical USB serial port
-    let skip_serial =
-        config.simulate || config.replay_file.is_some() || config.rtt || port_name == "BLE_STREAM";
-
-    let mut port = if let Some(p) = passed_port {
-        Some(p)
-    } else if skip_serial {
-        None
-    } else {
-        let data_bits = parse_data_bits(config.data_bits)?;
-        let stop_bits = parse_stop_bits(config.stop_bits)?;
-        let parity = parse_parity(&config.parity)?;
-        let flow_control = parse_flow_control(&config.flow_control)?;
-
-        Some(
-            serialport::new(&port_name, config.baud)
-                .timeout(Duration::from_millis(config.timeout_ms))
-                .data_bits(data_bits)
-                .stop_bits(stop_bits)
-                .parity(parity)
-                .flow_control(flow_control)
-                .open()?,
-        )
-    };
-    let mut rtt_reader = if let Some(r) = passed_rtt {
-        Some(r)
-    } else if config.rtt {
-        let elf = config.elf.as_deref().unwrap_or("");
-
-        if elf.is_empty() {
-            return Err("RTT mode requires an ELF file. Use --elf <path>".into());
+    // Surface configuration errors before taking over the terminal. A device
+    // that is merely absent is retried from the render loop instead.
+    let mut open_error = None;
+    if !source.is_open() {
+        match source.open() {
+            Ok(()) => {}
+            Err(OpenError::Fatal(e)) => return Err(e),
+            Err(OpenError::Unavailable(e)) => open_error = Some(e),
         }
-        Some(RttDefmtReader::new(elf, config.chip.clone())?)
-    } else {
-        None
-    };
+    }
+    let mut last_open_attempt = Instant::now();
 
     enable_raw_mode()?;
     let mut stdout = io::stdout();
@@ -395,28 +430,24 @@ pub fn run_plotter_mode(
             None
         };
 
-    std::thread::sleep(Duration::from_millis(config.reset_delay_ms));
-
-    if let Some(p) = port.as_mut() {
-        let _ = p.clear(serialport::ClearBuffer::Input);
-    }
-
     let csv_streamer = config
         .csv_file
         .as_ref()
         .and_then(|path| crate::export::CsvStreamer::new(path).ok());
 
-    let mut session_replayer = if let Some(ref path) = config.replay_file {
-        Some(
-            crate::replay::SessionReplayer::new(path)
-                .map_err(|e| format!("Failed to open replay file '{}' : {}", path, e))?,
-        )
-    } else {
-        None
+    let mut script = match config.script.as_deref().map(LineScript::load).transpose() {
+        Ok(script) => script,
+        Err(e) => {
+            disable_raw_mode().ok();
+            execute!(terminal.backend_mut(), LeaveAlternateScreen).ok();
+            return Err(e);
+        }
     };
 
     let mut state = PlotterState::new(config.export_limit, csv_streamer, config.obj_file);
-    let mut serial_buf = [0u8; 1024];
+    if let Some(e) = open_error {
+        state.last_error = Some(format!("Waiting for {}: {}", source.describe(), e));
+    }
 
     if let crate::config::BrailleModel::Custom(ref path) = config.braille {
         if path.to_lowercase().ends_with(".obj") {
@@ -472,12 +503,7 @@ pub fn run_plotter_mode(
                 KeyCode::Char('p') if key.modifiers.contains(KeyModifiers::CONTROL) => {
                     disable_raw_mode().ok();
                     execute!(terminal.backend_mut(), LeaveAlternateScreen).ok();
-                    return Ok(crate::AppExitState::SwitchToMonitor {
-                        port,
-                        rtt_reader,
-                        #[cfg(feature = "ble")]
-                        ble_rx: active_ble_rx,
-                    });
+                    return Ok(crate::AppExitState::SwitchToMonitor { source });
                 }
                 // Space: pause / resume
                 KeyCode::Char(' ') => {
@@ -532,6 +558,15 @@ pub fn run_plotter_mode(
                     }
                 }
 
+                // 't': cycle the channel tabs of a multi-channel source
+                KeyCode::Char('t') if state.channel_names.len() > 1 => {
+                    state.channel_filter = match state.channel_filter {
+                        None => Some(0),
+                        Some(i) if i + 1 < state.channel_names.len() => Some(i + 1),
+                        Some(_) => None,
+                    };
+                }
+
                 KeyCode::Up => {
                     state.sensor_scroll = state.sensor_scroll.saturating_sub(1);
                 }
@@ -543,155 +578,77 @@ pub fn run_plotter_mode(
             }
         }
 
-        // ── Serial read ───────────────────────────────────────────────────────
-
-        // The Fix: Decoupling the input streams into separate IF blocks instead of chained ELSE-IF
-
-        if config.simulate {
-            let t = state.x * 0.1;
-            let pitch = (t * 0.5).sin() * 45.0;
-            let roll = (t * 0.8).cos() * 30.0;
-            let yaw = (t * 2.0) % 360.0;
-
-            state.ingest_line(&format!("Pitch: {:.2}", pitch), config.plot_points);
-            state.ingest_line(&format!("Roll: {:.2}", roll), config.plot_points);
-            state.ingest_line(&format!("Yaw: {:.2}", yaw), config.plot_points);
-
-            std::thread::sleep(Duration::from_millis(50));
-        }
-
-        if let Some(ref mut replayer) = session_replayer {
-            match replayer.next_payload() {
-                crate::replay::ReplayEvent::Payload(payload) => {
-                    state.ingest_line(&payload, config.plot_points);
-                }
-                crate::replay::ReplayEvent::Waiting => {}
-                crate::replay::ReplayEvent::Eof => {
-                    std::thread::sleep(Duration::from_millis(100));
-                }
-            }
-        }
-
-        if let Some(reader) = rtt_reader.as_mut() {
-            // Drain RTT/DEFMT buffer
-            match reader.poll_logs() {
-                Ok(logs) => {
-                    for line in logs {
-                        if let Some(ref mut writer) = log_writer {
-                            let _ =
-                                writeln!(writer, "RX [{}]: {}", get_timestamp(), line.trim_end());
-                            let _ = writer.flush();
+        // ── Source read ───────────────────────────────────────────────────────
+        if source.is_open() {
+            // DRAIN LOOP: Pull all available data from the source before rendering
+            const MAX_DRAIN_SIZE: usize = 10;
+
+            for _ in 0..MAX_DRAIN_SIZE {
+                match source.read() {
+                    SourceEvent::Bytes(raw) => {
+                        let chunk = String::from_utf8_lossy(&raw);
+                        state.receive_buf.push_str(&chunk);
+
+                        while let Some(pos) = state.receive_buf.find('\n') {
+                            let line = state.receive_buf.drain(..=pos).collect::<String>();
+                            receive_line(
+                                &mut state,
+                                script.as_mut(),
+                                None,
+                                &line,
+                                config.plot_points,
+                                &mut source,
+                                &mut log_writer,
+                            );
                         }
-                        state.ingest_line(&line, config.plot_points);
                     }
-                }
-                Err(e) => {
-                    state.last_error = Some(format!("RTT lost: {}. Reconnecting...", e));
-
-                    let elf = config.elf.as_deref().unwrap_or("");
-                    match RttDefmtReader::new(elf, config.chip.clone()) {
-                        Ok(new_reader) => {
-                            *reader = new_reader;
-                            state.last_error = Some("RTT re-connected!".to_string());
-                        }
-                        Err(err) => {
-                            state.last_error = Some(format!("RTT re-connect failed: {}", err));
-
-                            std::thread::sleep(Duration::from_millis(500));
+                    SourceEvent::Lines(lines) => {
+                        for line in lines {
+                            receive_line(
+                                &mut state,
+                                script.as_mut(),
+                                None,
+                                &line,
+                                config.plot_points,
+                                &mut source,
+                                &mut log_writer,
+                            );
                         }
                     }
-                }
-            }
-        }
-
-        #[cfg(feature = "ble")]
-        {
-            if let Some(rx) = active_ble_rx.as_ref() {
-                // Fix #2: Limit the number of BLE messages processed per frame iteration to prevent UI lockups
-                let mut messages_processed = 0;
-                const MAX_MESSAGES_PER_FRAME: usize = 32;
-
-                while messages_processed < MAX_MESSAGES_PER_FRAME {
-                    if let Ok(event) = rx.try_recv() {
-                        // Fix #1 (Receiver): Catch the disconnect control string from ble.rs
-                        match event {
-                            crate::ble::BleEvent::Disconnected => {
-                                state.last_error = Some("❌ BLE Connection Lost.".to_string());
-                                break;
-                            }
-                            crate::ble::BleEvent::Payload(text) => {
-                                state.receive_buf.push_str(&text);
-                                messages_processed += 1;
-
-                                while let Some(pos) = state.receive_buf.find('\n') {
-                                    let line = state.receive_buf.drain(..=pos).collect::<String>();
-
-                                    if let Some(ref mut writer) = log_writer {
-                                        let _ = writeln!(
-                                            writer,
-                                            "RX [{}]: {}",
-                                            get_timestamp(),
-                                            line.trim_end()
-                                        );
-                                        let _ = writer.flush();
-                                    }
-
-                                    state.ingest_line(&line, config.plot_points);
-                                }
-                            }
+                    SourceEvent::ChannelLines(channel, lines) => {
+                        for line in lines {
+                            receive_line(
+                                &mut state,
+                                script.as_mut(),
+                                Some(channel),
+                                &line,
+                                config.plot_points,
+                                &mut source,
+                                &mut log_writer,
+                            );
                         }
-                    } else {
-                        break; // Channel is empty, exit the drain loop
+                    }
+                    SourceEvent::Idle | SourceEvent::Eof => break,
+                    SourceEvent::Disconnected(e) => {
+                        state.last_error = Some(format!("Connection lost: {}. Reconnecting...", e));
+                        last_open_attempt = Instant::now();
+                        break;
                     }
                 }
             }
-        }
-
-        if let Some(p) = port.as_mut() {
-            // DRAIN LOOP: Pull all available data from the OS buffer before rendering
-            let mut drain_iters = 0;
-            const MAX_DRAIN_SIZE: usize = 10; // 10 iterations * 1024 byte = 10kB max per frame
-
-            loop {
-                if drain_iters >= MAX_DRAIN_SIZE {
-                    break;
+        } else if last_open_attempt.elapsed() >= Duration::from_secs(1) {
+            last_open_attempt = Instant::now();
+            match source.reconnect() {
+                Ok(()) => {
+                    state.last_error = Some(format!("Re-connected to {}", source.describe()));
                 }
-
-                drain_iters += 1;
-                match p.bytes_to_read() {
-                    Ok(avail) if avail > 0 => match p.read(&mut serial_buf) {
-                        Ok(n) if n > 0 => {
-                            let chunk = String::from_utf8_lossy(&serial_buf[..n]);
-                            state.receive_buf.push_str(&chunk);
-
-                            while let Some(pos) = state.receive_buf.find('\n') {
-                                let line = state.receive_buf.drain(..=pos).collect::<String>();
-
-                                if let Some(ref mut writer) = log_writer {
-                                    let _ = writeln!(
-                                        writer,
-                                        "RX [{}]: {}",
-                                        get_timestamp(),
-                                        line.trim_end()
-                                    );
-                                    let _ = writer.flush();
-                                }
-
-                                state.ingest_line(&line, config.plot_points);
-                            }
-                        }
-                        Ok(_) => break,
-                        Err(ref e) if e.kind() == io::ErrorKind::TimedOut => break,
-                        Err(e) => {
-                            state.last_error = Some(format!("Read error: {}", e));
-                            break;
-                        }
-                    },
-                    Ok(_) => break, // Break the drain loop if the OS buffer is empty
-                    Err(e) => {
-                        state.last_error = Some(format!("Read error: {}", e));
-                        break;
-                    }
+                Err(OpenError::Fatal(e)) => {
+                    disable_raw_mode().ok();
+                    execute!(terminal.backend_mut(), LeaveAlternateScreen).ok();
+                    return Err(e);
+                }
+                Err(OpenError::Unavailable(e)) => {
+                    state.last_error = Some(format!("Waiting for {}: {}", source.describe(), e));
                 }
             }
         }
@@ -740,8 +697,15 @@ pub fn run_plotter_mode(
         let total_samples = state.total_samples;
         let sensor_count = state.sensors.len();
         let last_error = state.last_error.clone();
-        let baud = config.baud;
-        let port_name_disp = port_name.clone();
+        let source_name = source.describe();
+        let frame_stats = source.frame_stats();
+        state.channel_names = source.channels();
+        if state
+            .channel_filter
+            .is_some_and(|i| i >= state.channel_names.len())
+        {
+            state.channel_filter = None;
+        }
 
         // Build sidebar rows before the draw closure
         let sidebar_rows: Vec<(String, Color, f64, f64, f64)> = state
@@ -781,16 +745,38 @@ pub fn run_plotter_mode(
                 .constraints([Constraint::Min(3), Constraint::Length(3)])
                 .split(f.area());
 
+            // Channel tabs above the chart when the source has several
+            let mut plot_area = outer[0];
+            if state.channel_names.len() > 1 {
+                let rows = Layout::default()
+                    .direction(Direction::Vertical)
+                    .constraints([Constraint::Length(1), Constraint::Min(3)])
+                    .split(outer[0]);
+                let titles = std::iter::once("All".to_string())
+                    .chain(state.channel_names.iter().cloned())
+                    .collect::<Vec<_>>();
+                let tabs = Tabs::new(titles)
+                    .select(state.channel_filter.map_or(0, |i| i + 1))
+                    .style(Style::default().fg(Color::DarkGray))
+                    .highlight_style(
+                        Style::default()
+                            .fg(Color::Cyan)
+                            .add_modifier(Modifier::BOLD),
+                    );
+                f.render_widget(tabs, rows[0]);
+                plot_area = rows[1];
+            }
+
             let main_row = Layout::default()
                 .direction(Direction::Horizontal)
                 .constraints([Constraint::Min(10), Constraint::Length(28)])
-                .split(outer[0]);
+                .split(plot_area);
 
             match state.active_tab {
                 ActiveTab::Chart2D => {
                     let chart_title = format!(
-                        " 󰕾 ComChan Plotter  {}  {}  {} sensors{}",
-                        port_name_disp, baud, sensor_count, pause_indicator
+                        " 󰕾 ComChan Plotter  {}  {} sensors{}",
+                        source_name, sensor_count, pause_indicator
                     );
 
                     let chart = Chart::new(datasets)
@@ -1060,7 +1046,7 @@ pub fn run_plotter_mode(
                 Span::raw("")
             };
 
-            let status_line = Line::from(vec![
+            let mut status_spans = vec![
                 Span::styled(format!(" ⏱ {}", uptime), Style::default().fg(Color::Green)),
                 Span::raw("  "),
                 Span::styled(
@@ -1073,6 +1059,30 @@ pub fn run_plotter_mode(
                     Style::default().fg(Color::Yellow),
                 ),
                 Span::raw("  "),
+            ];
+
+            // Frame / decode error counters for `--framing`
+            if let Some(stats) = frame_stats {
+                let error_style = if stats.frame_errors + stats.decode_errors > 0 {
+                    Style::default().fg(Color::Red)
+                } else {
+                    Style::default().fg(Color::DarkGray)
+                };
+                status_spans.push(Span::styled(
+                    format!("󰓅 {} {} frames", stats.framing, stats.frames),
+                    Style::default().fg(Color::Blue),
+                ));
+                status_spans.push(Span::styled(
+                    format!(
+                        " ({} frame err, {} decode err)",
+                        stats.frame_errors, stats.decode_errors
+                    ),
+                    error_style,
+                ));
+                status_spans.push(Span::raw("  "));
+            }
+
+            status_spans.extend([
                 error_span,
                 Span::styled(
                     format!(" 🖥 {} ", state.terminal_type),
@@ -1082,6 +1092,7 @@ pub fn run_plotter_mode(
                 ),
                 Span::styled("  Press '?' for help ", Style::default().fg(Color::Cyan)),
             ]);
+            let status_line = Line::from(status_spans);
 
             let status_bar = Paragraph::new(status_line)
                 .block(
@@ -1102,6 +1113,7 @@ pub fn run_plotter_mode(
                     Line::from(" [?]          : Show / Hide this menu"),
                     Line::from(" [1] / [2]    : Switch between 2D Chart / 3D Wireframe"),
                     Line::from(" [Tab]        : Toggle views"),
+                    Line::from(" [t]          : Next channel tab (multi-channel RTT)"),
                     Line::from(" [Space]      : Pause / Resume data flow"),
                     Line::from(" [c]          : Clear all plotter data"),
                     Line::from(" [Ctrl+S]     : Export 2D Chart to SVG"),
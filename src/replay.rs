@@ -105,3 +105,60 @@ impl SessionReplayer {
         ReplayEvent::Eof
     }
 }
+
+/// [`DataSource`](crate::source::DataSource) adapter that plays back a
+/// recorded `.log` / `.csv` session.
+pub struct ReplaySource {
+    path: String,
+    replayer: Option<SessionReplayer>,
+}
+
+impl ReplaySource {
+    pub fn new(path: &str) -> Self {
+        Self {
+            path: path.to_string(),
+            replayer: None,
+        }
+    }
+}
+
+impl crate::source::DataSource for ReplaySource {
+    fn open(&mut self) -> Result<(), crate::source::OpenError> {
+        let replayer = SessionReplayer::new(&self.path).map_err(|e| {
+            crate::source::OpenError::Fatal(
+                format!("Failed to open replay file '{}': {}", self.path, e).into(),
+            )
+        })?;
+        self.replayer = Some(replayer);
+        Ok(())
+    }
+
+    fn is_open(&self) -> bool {
+        self.replayer.is_some()
+    }
+
+    fn close(&mut self) {
+        self.replayer = None;
+    }
+
+    fn read(&mut self) -> crate::source::SourceEvent {
+        use crate::source::SourceEvent;
+
+        match self.replayer.as_mut().map(|r| r.next_payload()) {
+            Some(ReplayEvent::Payload(payload)) => SourceEvent::Lines(vec![payload]),
+            Some(ReplayEvent::Waiting) | None => SourceEvent::Idle,
+            Some(ReplayEvent::Eof) => SourceEvent::Eof,
+        }
+    }
+
+    fn write(&mut self, _data: &[u8]) -> std::io::Result<()> {
+        Err(std::io::Error::new(
+            std::io::ErrorKind::Unsupported,
+            "Sending Input during a replay is not supported",
+        ))
+    }
+
+    fn describe(&self) -> String {
+        format!("Replay: {}", self.path)
+    }
+}
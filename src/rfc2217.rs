@@ -0,0 +1,400 @@
+use crate::config::MergedConfig;
+use crate::serial::{parse_data_bits, parse_flow_control, parse_parity, parse_stop_bits};
+use crate::source::{DataSource, OpenError, SourceEvent};
+use serialport::{DataBits, FlowControl, Parity, StopBits};
+use std::io::{self, Read};
+use std::net::TcpStream;
+use std::thread;
+use std::time::{Duration, Instant};
+
+pub const RFC2217_SCHEME: &str = "rfc2217://";
+
+// ── Telnet (RFC 854) ──────────────────────────────────────────────────────────
+const IAC: u8 = 255;
+const DONT: u8 = 254;
+const DO: u8 = 253;
+const WONT: u8 = 252;
+const WILL: u8 = 251;
+const SB: u8 = 250;
+const SE: u8 = 240;
+
+const OPT_BINARY: u8 = 0;
+const OPT_SGA: u8 = 3;
+const OPT_COM_PORT: u8 = 44;
+
+// ── COM-PORT-OPTION (RFC 2217) ────────────────────────────────────────────────
+const SET_BAUDRATE: u8 = 1;
+const SET_DATASIZE: u8 = 2;
+const SET_PARITY: u8 = 3;
+const SET_STOPSIZE: u8 = 4;
+const SET_CONTROL: u8 = 5;
+/// Servers answer every client command with the same code plus 100.
+const SERVER_OFFSET: u8 = 100;
+
+const CONTROL_FLOW_NONE: u8 = 1;
+const CONTROL_FLOW_XONXOFF: u8 = 2;
+const CONTROL_FLOW_HARDWARE: u8 = 3;
+const CONTROL_DTR_ON: u8 = 8;
+const CONTROL_DTR_OFF: u8 = 9;
+
+/// Something the Telnet layer pulled out of the byte stream.
+enum TelnetEvent {
+    /// `IAC WILL/WONT/DO/DONT <option>`
+    Negotiation(u8, u8),
+    /// Payload of `IAC SB ... IAC SE` with escaping removed.
+    Subnegotiation(Vec<u8>),
+}
+
+#[derive(Clone, Copy)]
+enum DecodeState {
+    Data,
+    Iac,
+    Negotiate(u8),
+    Sub,
+    SubIac,
+}
+
+/// Incremental Telnet decoder; state survives across TCP reads so commands
+/// split over packet boundaries are handled.
+struct TelnetDecoder {
+    state: DecodeState,
+    sub_buf: Vec<u8>,
+}
+
+impl TelnetDecoder {
+    fn new() -> Self {
+        Self {
+            state: DecodeState::Data,
+            sub_buf: Vec::new(),
+        }
+    }
+
+    fn feed(&mut self, input: &[u8], data: &mut Vec<u8>, events: &mut Vec<TelnetEvent>) {
+        for &byte in input {
+            self.state = match (self.state, byte) {
+                (DecodeState::Data, IAC) => DecodeState::Iac,
+                (DecodeState::Data, _) => {
+                    data.push(byte);
+                    DecodeState::Data
+                }
+                // IAC IAC is an escaped 0xFF data byte
+                (DecodeState::Iac, IAC) => {
+                    data.push(IAC);
+                    DecodeState::Data
+                }
+                (DecodeState::Iac, WILL | WONT | DO | DONT) => DecodeState::Negotiate(byte),
+                (DecodeState::Iac, SB) => {
+                    self.sub_buf.clear();
+                    DecodeState::Sub
+                }
+                // NOP, GA and friends carry no meaning for a serial stream
+                (DecodeState::Iac, _) => DecodeState::Data,
+                (DecodeState::Negotiate(cmd), option) => {
+                    events.push(TelnetEvent::Negotiation(cmd, option));
+                    DecodeState::Data
+                }
+                (DecodeState::Sub, IAC) => DecodeState::SubIac,
+                (DecodeState::Sub, _) => {
+                    self.sub_buf.push(byte);
+                    DecodeState::Sub
+                }
+                (DecodeState::SubIac, IAC) => {
+                    self.sub_buf.push(IAC);
+                    DecodeState::Sub
+                }
+                (DecodeState::SubIac, SE) => {
+                    events.push(TelnetEvent::Subnegotiation(std::mem::take(
+                        &mut self.sub_buf,
+                    )));
+                    DecodeState::Data
+                }
+                // Malformed: drop the subnegotiation and resync on plain data
+                (DecodeState::SubIac, _) => DecodeState::Data,
+            };
+        }
+    }
+}
+
+/// Doubles every 0xFF so payload bytes are never mistaken for Telnet commands.
+fn escape_iac(data: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(data.len());
+    for &byte in data {
+        out.push(byte);
+        if byte == IAC {
+            out.push(IAC);
+        }
+    }
+    out
+}
+
+fn com_port_command(command: u8, value: &[u8]) -> Vec<u8> {
+    let mut out = vec![IAC, SB, OPT_COM_PORT, command];
+    out.extend(escape_iac(value));
+    out.extend([IAC, SE]);
+    out
+}
+
+/// Serial settings translated into their RFC 2217 wire values.
+struct ComPortSettings {
+    baud: u32,
+    data_size: u8,
+    parity: u8,
+    stop_size: u8,
+    flow_control: u8,
+}
+
+impl ComPortSettings {
+    fn from_config(config: &MergedConfig) -> Result<Self, String> {
+        let data_bits =
+            parse_data_bits(config.data_bits).map_err(|e| format!("Configuration error: {}", e))?;
+        let stop_bits =
+            parse_stop_bits(config.stop_bits).map_err(|e| format!("Configuration error: {}", e))?;
+        let parity =
+            parse_parity(&config.parity).map_err(|e| format!("Configuration error: {}", e))?;
+        let flow_control = parse_flow_control(&config.flow_control)
+            .map_err(|e| format!("Configuration error: {}", e))?;
+
+        Ok(Self {
+            baud: config.baud,
+            data_size: match data_bits {
+                DataBits::Five => 5,
+                DataBits::Six => 6,
+                DataBits::Seven => 7,
+                DataBits::Eight => 8,
+            },
+            parity: match parity {
+                Parity::None => 1,
+                Parity::Odd => 2,
+                Parity::Even => 3,
+            },
+            stop_size: match stop_bits {
+                StopBits::One => 1,
+                StopBits::Two => 2,
+            },
+            flow_control: match flow_control {
+                FlowControl::None => CONTROL_FLOW_NONE,
+                FlowControl::Software => CONTROL_FLOW_XONXOFF,
+                FlowControl::Hardware => CONTROL_FLOW_HARDWARE,
+            },
+        })
+    }
+
+    fn commands(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        out.extend(com_port_command(SET_BAUDRATE, &self.baud.to_be_bytes()));
+        out.extend(com_port_command(SET_DATASIZE, &[self.data_size]));
+        out.extend(com_port_command(SET_PARITY, &[self.parity]));
+        out.extend(com_port_command(SET_STOPSIZE, &[self.stop_size]));
+        out.extend(com_port_command(SET_CONTROL, &[self.flow_control]));
+        out
+    }
+}
+
+/// Telnet COM port control client (`rfc2217://host:port`). Applies the
+/// configured baud rate, framing and flow control on the remote port.
+pub struct Rfc2217Source {
+    address: String,
+    settings: ComPortSettings,
+    timeout: Duration,
+    reset_delay: Duration,
+    stream: Option<TcpStream>,
+    decoder: TelnetDecoder,
+    /// Data that arrived while the options were still being negotiated.
+    pending: Vec<u8>,
+    /// Baud rate the server reported back, if it did.
+    remote_baud: Option<u32>,
+    buffer: [u8; 1024],
+}
+
+impl Rfc2217Source {
+    /// `address` is the `host:port` part of an `rfc2217://host:port` URL.
+    pub fn new(config: &MergedConfig, address: &str) -> Result<Self, String> {
+        Ok(Self {
+            address: address.to_string(),
+            settings: ComPortSettings::from_config(config)?,
+            timeout: Duration::from_millis(config.timeout_ms.max(1)),
+            reset_delay: Duration::from_millis(config.reset_delay_ms),
+            stream: None,
+            decoder: TelnetDecoder::new(),
+            pending: Vec::new(),
+            remote_baud: None,
+            buffer: [0u8; 1024],
+        })
+    }
+
+    fn send_raw(&mut self, bytes: &[u8]) -> io::Result<()> {
+        let stream = self
+            .stream
+            .as_mut()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotConnected, "socket is not open"))?;
+        crate::tcp::write_blocking(stream, bytes)
+    }
+
+    /// Answers option requests we did not initiate and records server
+    /// replies. Returns `Err` when the server refuses COM port control.
+    fn handle_events(&mut self, events: Vec<TelnetEvent>) -> io::Result<()> {
+        for event in events {
+            match event {
+                TelnetEvent::Negotiation(DONT, OPT_COM_PORT) => {
+                    return Err(io::Error::new(
+                        io::ErrorKind::Unsupported,
+                        "server refused RFC 2217 COM port control",
+                    ));
+                }
+                // Acknowledgements of options we asked for need no reply
+                TelnetEvent::Negotiation(DO, OPT_BINARY | OPT_SGA | OPT_COM_PORT)
+                | TelnetEvent::Negotiation(WILL, OPT_BINARY | OPT_SGA)
+                | TelnetEvent::Negotiation(WONT | DONT, _) => {}
+                TelnetEvent::Negotiation(DO, option) => self.send_raw(&[IAC, WONT, option])?,
+                TelnetEvent::Negotiation(WILL, option) => self.send_raw(&[IAC, DONT, option])?,
+                TelnetEvent::Negotiation(_, _) => {}
+                TelnetEvent::Subnegotiation(payload) => {
+                    if let [OPT_COM_PORT, command, value @ ..] = payload.as_slice()
+                        && *command == SET_BAUDRATE + SERVER_OFFSET
+                        && let Ok(bytes) = <[u8; 4]>::try_from(value)
+                    {
+                        self.remote_baud = Some(u32::from_be_bytes(bytes));
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Reads whatever is on the socket, splitting payload from Telnet traffic.
+    fn poll_socket(&mut self) -> io::Result<Vec<u8>> {
+        let Some(stream) = self.stream.as_mut() else {
+            return Ok(Vec::new());
+        };
+
+        let n = match stream.read(&mut self.buffer) {
+            Ok(0) => {
+                return Err(io::Error::new(
+                    io::ErrorKind::ConnectionAborted,
+                    "Connection closed by remote host",
+                ));
+            }
+            Ok(n) => n,
+            Err(ref e)
+                if e.kind() == io::ErrorKind::WouldBlock
+                    || e.kind() == io::ErrorKind::Interrupted =>
+            {
+                return Ok(Vec::new());
+            }
+            Err(e) => return Err(e),
+        };
+
+        let mut data = Vec::new();
+        let mut events = Vec::new();
+        self.decoder.feed(&self.buffer[..n], &mut data, &mut events);
+        self.handle_events(events)?;
+        Ok(data)
+    }
+
+    fn negotiate(&mut self) -> io::Result<()> {
+        let mut request = vec![
+            IAC,
+            WILL,
+            OPT_BINARY,
+            IAC,
+            DO,
+            OPT_BINARY,
+            IAC,
+            WILL,
+            OPT_SGA,
+            IAC,
+            DO,
+            OPT_SGA,
+            IAC,
+            WILL,
+            OPT_COM_PORT,
+        ];
+        request.extend(self.settings.commands());
+        // Mirror the local open sequence: drop DTR so auto-reset boards reboot
+        request.extend(com_port_command(SET_CONTROL, &[CONTROL_DTR_OFF]));
+        self.send_raw(&request)?;
+
+        // Give the server one timeout period to confirm the baud rate. Silent
+        // servers are tolerated; an explicit refusal is not.
+        let deadline = Instant::now() + self.timeout;
+        while self.remote_baud.is_none() && Instant::now() < deadline {
+            let data = self.poll_socket()?;
+            self.pending.extend(data);
+            thread::sleep(Duration::from_millis(5));
+        }
+        Ok(())
+    }
+}
+
+impl DataSource for Rfc2217Source {
+    fn open(&mut self) -> Result<(), OpenError> {
+        self.stream = Some(crate::tcp::connect(&self.address, self.timeout)?);
+        self.decoder = TelnetDecoder::new();
+        self.pending.clear();
+        self.remote_baud = None;
+
+        if let Err(e) = self.negotiate() {
+            self.stream = None;
+            return Err(if e.kind() == io::ErrorKind::Unsupported {
+                OpenError::Fatal(format!("{}: {}", self.describe(), e).into())
+            } else {
+                OpenError::Unavailable(e.to_string())
+            });
+        }
+
+        thread::sleep(self.reset_delay);
+        let _ = self.send_raw(b"\r");
+        Ok(())
+    }
+
+    fn is_open(&self) -> bool {
+        self.stream.is_some()
+    }
+
+    fn close(&mut self) {
+        self.stream = None;
+    }
+
+    fn read(&mut self) -> SourceEvent {
+        if self.stream.is_none() {
+            return SourceEvent::Idle;
+        }
+
+        let mut data = std::mem::take(&mut self.pending);
+        match self.poll_socket() {
+            Ok(more) => data.extend(more),
+            Err(e) => {
+                self.stream = None;
+                return SourceEvent::Disconnected(e.to_string());
+            }
+        }
+
+        if data.is_empty() {
+            SourceEvent::Idle
+        } else {
+            SourceEvent::Bytes(data)
+        }
+    }
+
+    fn write(&mut self, data: &[u8]) -> io::Result<()> {
+        self.send_raw(&escape_iac(data))
+    }
+
+    fn set_dtr(&mut self, level: bool) -> io::Result<()> {
+        let state = if level {
+            CONTROL_DTR_ON
+        } else {
+            CONTROL_DTR_OFF
+        };
+        self.send_raw(&com_port_command(SET_CONTROL, &[state]))
+    }
+
+    fn is_serial(&self) -> bool {
+        true
+    }
+
+    fn describe(&self) -> String {
+        let baud = self.remote_baud.unwrap_or(self.settings.baud);
+        format!("{}{} @ {} baud", RFC2217_SCHEME, self.address, baud)
+    }
+}
@@ -1,155 +1,568 @@
-use defmt_decoder::{DecodeError, StreamDecoder, Table};
+use crate::config::{MergedConfig, RttChannelSpec, RttDecoder};
+use crate::defmt_stream::{DefmtDecoder, DefmtOptions};
+use crate::framing::{FrameDecoder, FrameStats};
+use inquire::Select;
 use object::{Object, ObjectSymbol};
 use probe_rs::{
     Permissions, Session,
     config::TargetSelector,
-    probe::list::Lister,
+    flashing::{FormatKind, download_file},
+    probe::{DebugProbeSelector, list::Lister},
     rtt::{Rtt, ScanRegion},
 };
+use std::collections::VecDeque;
 use std::fs;
+use std::io;
+use std::io::IsTerminal;
+use std::time::{Duration, Instant};
+
+/// How long a write waits for the target to drain a full down-channel.
+const DOWN_CHANNEL_TIMEOUT: Duration = Duration::from_millis(500);
+
+/// How long to look for the RTT control block after flashing or a reset,
+/// while the firmware starts up.
+const ATTACH_AFTER_RESET: Duration = Duration::from_secs(2);
+
+/// Which probe, chip and core to attach to and how (`--probe`, `--chip`,
+/// `--core`, `--attach-under-reset`, `--reset-halt`, `--flash`).
+pub struct ProbeSetup {
+    chip: Option<String>,
+    probe: Option<DebugProbeSelector>,
+    core: usize,
+    attach_under_reset: bool,
+    /// `flash` and `reset_halt` are cleared after the first successful attach
+    /// so reattaching never reprograms or resets the target.
+    flash: bool,
+    reset_halt: bool,
+}
+
+impl ProbeSetup {
+    /// Resolves the probe up front, asking the user to pick one when several
+    /// are connected and `--probe` was not given.
+    pub fn from_config(config: &MergedConfig) -> Result<Self, Box<dyn std::error::Error>> {
+        let probe = match config.probe {
+            Some(ref selector) => Some(selector.clone()),
+            None => pick_probe()?,
+        };
+        Ok(Self {
+            chip: config.chip.clone(),
+            probe,
+            core: config.rtt_core,
+            attach_under_reset: config.attach_under_reset,
+            flash: config.flash,
+            reset_halt: config.reset_halt,
+        })
+    }
+}
+
+/// `None` leaves the choice to attach time: zero probes (yet) or exactly one.
+fn pick_probe() -> Result<Option<DebugProbeSelector>, Box<dyn std::error::Error>> {
+    let mut probes = Lister::new().list_all();
+    if probes.len() < 2 {
+        return Ok(None);
+    }
+    if !std::io::stdin().is_terminal() {
+        return Err(format!(
+            "{} debug probes detected. Pick one with --probe VID:PID:SERIAL",
+            probes.len()
+        )
+        .into());
+    }
+
+    let display_list: Vec<String> = probes
+        .iter()
+        .map(|p| {
+            format!(
+                "{} [{:04x}:{:04x}:{}]",
+                p.identifier,
+                p.vendor_id,
+                p.product_id,
+                p.serial_number.as_deref().unwrap_or("-")
+            )
+        })
+        .collect();
+    let selection = Select::new("Select debug probe: ", display_list.clone())
+        .with_page_size(10)
+        .prompt()?;
+    let index = display_list.iter().position(|r| r == &selection).unwrap();
+    Ok(Some(DebugProbeSelector::from(probes.remove(index))))
+}
+
+/// Which decoder each RTT up-channel gets (`--rtt-channel`), plus the
+/// settings those decoders are built from.
+#[derive(Clone)]
+pub struct ChannelSetup {
+    specs: Vec<RttChannelSpec>,
+    /// Down-channel that input is written to (`--rtt-down`).
+    down: usize,
+    defmt: DefmtOptions,
+    /// Payload and length-framing options for framed channels.
+    config: MergedConfig,
+}
+
+impl ChannelSetup {
+    pub fn from_config(config: &MergedConfig) -> Result<Self, Box<dyn std::error::Error>> {
+        let setup = Self {
+            specs: config.rtt_channels.clone(),
+            down: config.rtt_down,
+            defmt: DefmtOptions::from_config(config),
+            config: config.clone(),
+        };
+        // Surface a bad --schema or --frame-sync before touching the probe
+        for spec in &setup.specs {
+            if let RttDecoder::Framed(framing) = spec.decoder {
+                FrameDecoder::new(config, framing)?;
+            }
+        }
+        Ok(setup)
+    }
+
+    /// Channel 0 carries defmt unless told otherwise; the rest are text.
+    fn decoder_for(&self, channel: usize) -> RttDecoder {
+        self.specs
+            .iter()
+            .rev()
+            .find(|spec| spec.channel == channel)
+            .map(|spec| spec.decoder)
+            .unwrap_or(if channel == 0 {
+                RttDecoder::Defmt
+            } else {
+                RttDecoder::Text
+            })
+    }
+}
+
+/// Lines completed on one up-channel, tagged with its index.
+type ChannelBatch = (usize, Vec<String>);
+
+/// Turns the bytes of one up-channel into lines.
+enum ChannelDecoder {
+    Defmt(DefmtDecoder),
+    /// Newline-separated text; holds the unfinished last line.
+    Text(String),
+    Framed(FrameDecoder),
+}
+
+impl ChannelDecoder {
+    fn received(&mut self, bytes: &[u8]) -> Vec<String> {
+        match self {
+            ChannelDecoder::Defmt(decoder) => decoder.received(bytes),
+            ChannelDecoder::Framed(decoder) => decoder.received(bytes),
+            ChannelDecoder::Text(partial) => {
+                partial.push_str(&String::from_utf8_lossy(bytes));
+                let mut lines = Vec::new();
+                while let Some(pos) = partial.find('\n') {
+                    let line: String = partial.drain(..=pos).collect();
+                    lines.push(line.trim_end_matches(['\r', '\n']).to_string());
+                }
+                lines
+            }
+        }
+    }
+}
 
 pub struct RttDefmtReader {
     session: Session,
     rtt: Rtt,
-    stream_decoder: Box<dyn StreamDecoder>,
+    /// One decoder per up-channel, in channel order.
+    decoders: Vec<ChannelDecoder>,
+    names: Vec<String>,
+    core: usize,
+    down: usize,
 }
 
 impl RttDefmtReader {
     pub fn new(
         elf_path: &str,
-        chip_override: Option<String>,
+        probe_setup: &mut ProbeSetup,
+        setup: &ChannelSetup,
     ) -> Result<Self, Box<dyn std::error::Error>> {
         let elf_bytes = fs::read(elf_path)?;
         let elf_bytes_ref: &'static [u8] = Box::leak(elf_bytes.into_boxed_slice());
 
-        let table = Table::parse(elf_bytes_ref)?
-            .ok_or("No defmt table found. Is the firmware compiled with defmt?")?;
-
-        let table_ref: &'static Table = Box::leak(Box::new(table));
-        let stream_decoder = table_ref.new_stream_decoder();
+        // Parsed before attaching, so a firmware without defmt fails fast
+        let uses_defmt = setup.decoder_for(0) == RttDecoder::Defmt
+            || setup.specs.iter().any(|s| s.decoder == RttDecoder::Defmt);
+        let defmt = if uses_defmt {
+            Some(DefmtDecoder::from_elf(elf_bytes_ref, setup.defmt.clone())?)
+        } else {
+            None
+        };
 
         // ── INSTANT ATTACH FIX ──
         // Instead of scanning all of RAM, we parse the ELF file to find the exact
         // memory address of the RTT control block. This reduces attach time from 3s to 0.01s.
-        let rtt_addr = {
-            let obj_file = object::File::parse(elf_bytes_ref)?;
+        let obj_file = object::File::parse(elf_bytes_ref)?;
+        let symbol = |name: &str| {
             obj_file
                 .symbols()
-                .find(|sym| sym.name() == Ok("_SEGGER_RTT"))
-                .map(|sym| sym.address() as u32)
+                .find(|sym| sym.name() == Ok(name))
+                .map(|sym| sym.address())
         };
-
-        let lister = Lister::new();
-        let probes = lister.list_all();
-
-        if probes.is_empty() {
-            return Err("No debug probes detected. Check your USB connection.".into());
-        }
-
-        if probes.len() > 1 {
-            eprintln!("⚠️ Warning: Multiple debug probes detected!");
-            eprintln!(
-                "⚠️ Silently attaching to the first enumerated probe: {}",
-                probes[0].identifier
-            );
+        let rtt_addr = symbol("_SEGGER_RTT").map(|addr| addr as u32);
+        // Thumb function symbols carry the mode in bit 0
+        let main_addr = symbol("main").map(|addr| addr & !1);
+        if probe_setup.reset_halt && main_addr.is_none() {
+            return Err(format!("--reset-halt needs a `main` symbol in {}", elf_path).into());
         }
 
-        let probe = probes[0].open()?;
+        let lister = Lister::new();
+        let probe = match probe_setup.probe {
+            Some(ref selector) => lister
+                .open(selector)
+                .map_err(|e| format!("Failed to open probe {}: {}", selector, e))?,
+            None => {
+                let probes = lister.list_all();
+                if probes.is_empty() {
+                    return Err("No debug probes detected. Check your USB connection.".into());
+                }
+                if probes.len() > 1 {
+                    return Err(format!(
+                        "{} debug probes detected. Pick one with --probe VID:PID:SERIAL",
+                        probes.len()
+                    )
+                    .into());
+                }
+                probes[0].open()?
+            }
+        };
 
-        let target_selector = match chip_override {
-            Some(chip) => TargetSelector::Unspecified(chip),
+        let target_selector = match probe_setup.chip {
+            Some(ref chip) => TargetSelector::Unspecified(chip.clone()),
             None => TargetSelector::Auto,
         };
 
-        let mut session = probe.attach(target_selector, Permissions::default()).map_err(|e| {
+        let attached = if probe_setup.attach_under_reset {
+            probe.attach_under_reset(target_selector, Permissions::default())
+        } else {
+            probe.attach(target_selector, Permissions::default())
+        };
+        let mut session = attached.map_err(|e| {
             format!(
                 "Failed to attach: {}\nHint: Try specifying the chip manually using --chip (e.g., --chip nRF52840_xxAA)",
                 e
             )
         })?;
 
-        let rtt = {
-            let mut core = session.core(0)?;
+        let core = probe_setup.core;
+        let cores = session.list_cores();
+        if !cores.iter().any(|(index, _)| *index == core) {
+            return Err(format!(
+                "--core {} is out of range; this chip has {} core(s)",
+                core,
+                cores.len()
+            )
+            .into());
+        }
+
+        if probe_setup.flash {
+            // Progress goes to stderr so `--headless` output stays clean
+            eprint!("\r⚡ Flashing {}...\r\n", elf_path);
+            let started = Instant::now();
+            download_file(&mut session, elf_path, FormatKind::Elf)
+                .map_err(|e| format!("Failed to flash {}: {}", elf_path, e))?;
+            eprint!(
+                "\r✅ Flashed in {:.1}s\r\n",
+                started.elapsed().as_secs_f32()
+            );
+        }
 
-            if let Some(addr) = rtt_addr {
-                // Fast path: We know exactly where it is!
-                Rtt::attach_region(&mut core, &ScanRegion::Exact(addr as u64))?
+        let mut halted = false;
+        let attach_deadline = if probe_setup.flash || probe_setup.reset_halt {
+            let mut target = session.core(core)?;
+            if let Some(main_addr) = main_addr.filter(|_| probe_setup.reset_halt) {
+                // Run the startup code up to `main`, so static data such as a
+                // defmt control block is in place before the firmware logs
+                target.reset_and_halt(Duration::from_millis(500))?;
+                target
+                    .set_hw_breakpoint(main_addr)
+                    .map_err(|e| format!("Failed to set a breakpoint at main: {}", e))?;
+                target.run()?;
+                let reached = target.wait_for_core_halted(ATTACH_AFTER_RESET);
+                target.clear_hw_breakpoint(main_addr)?;
+                reached.map_err(|e| format!("Target did not reach main: {}", e))?;
+                halted = true;
             } else {
-                // Slow path fallback: Scan RAM, taking the highest address to avoid Flash mirrors
-                match Rtt::attach_region(&mut core, &ScanRegion::Ram) {
-                    Ok(rtt) => rtt,
-                    Err(probe_rs::rtt::Error::MultipleControlBlocksFound(addrs)) => {
-                        let active_addr = addrs.into_iter().max().expect("Address list empty");
-                        Rtt::attach_region(&mut core, &ScanRegion::Exact(active_addr))?
-                    }
-                    Err(e) => return Err(e.into()),
+                target.reset()?;
+            }
+            Some(Instant::now() + ATTACH_AFTER_RESET)
+        } else {
+            None
+        };
+
+        let mut rtt = loop {
+            match Self::attach_rtt(&mut session, core, rtt_addr) {
+                Ok(rtt) => break rtt,
+                // Runtimes such as rtt-target set the control block up in `main`
+                Err(_) if halted => {
+                    session.core(core)?.run()?;
+                    halted = false;
                 }
+                // The restarted firmware may not have set up its control block yet
+                Err(_) if attach_deadline.is_some_and(|d| Instant::now() < d) => {
+                    std::thread::sleep(Duration::from_millis(10));
+                }
+                Err(e) => return Err(e),
             }
         };
+        if halted {
+            session.core(core)?.run()?;
+        }
+        probe_setup.flash = false;
+        probe_setup.reset_halt = false;
+
+        let mut decoders = Vec::new();
+        let mut names = Vec::new();
+        for channel in rtt.up_channels().iter() {
+            let number = channel.number();
+            let decoder = match setup.decoder_for(number) {
+                RttDecoder::Defmt => match defmt {
+                    Some(ref defmt) => ChannelDecoder::Defmt(defmt.fresh()),
+                    None => unreachable!("defmt table is loaded whenever a channel uses it"),
+                },
+                RttDecoder::Text => ChannelDecoder::Text(String::new()),
+                RttDecoder::Framed(framing) => {
+                    ChannelDecoder::Framed(FrameDecoder::new(&setup.config, framing)?)
+                }
+            };
+            decoders.push(decoder);
+            names.push(match channel.name() {
+                Some(name) if !name.is_empty() => format!("{}:{}", number, name),
+                _ => number.to_string(),
+            });
+        }
 
         Ok(Self {
             session,
             rtt,
-            stream_decoder,
+            decoders,
+            names,
+            core,
+            down: setup.down,
         })
     }
 
-    pub fn poll_logs(&mut self) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+    fn attach_rtt(
+        session: &mut Session,
+        core: usize,
+        rtt_addr: Option<u32>,
+    ) -> Result<Rtt, Box<dyn std::error::Error>> {
+        let mut core = session.core(core)?;
+
+        if let Some(addr) = rtt_addr {
+            // Fast path: We know exactly where it is!
+            Ok(Rtt::attach_region(
+                &mut core,
+                &ScanRegion::Exact(addr as u64),
+            )?)
+        } else {
+            // Slow path fallback: Scan RAM, taking the highest address to avoid Flash mirrors
+            match Rtt::attach_region(&mut core, &ScanRegion::Ram) {
+                Ok(rtt) => Ok(rtt),
+                Err(probe_rs::rtt::Error::MultipleControlBlocksFound(addrs)) => {
+                    let active_addr = addrs.into_iter().max().expect("Address list empty");
+                    Ok(Rtt::attach_region(
+                        &mut core,
+                        &ScanRegion::Exact(active_addr),
+                    )?)
+                }
+                Err(e) => Err(e.into()),
+            }
+        }
+    }
+
+    /// Reads every up-channel once and returns the lines each one completed.
+    pub fn poll_logs(&mut self) -> Result<Vec<ChannelBatch>, Box<dyn std::error::Error>> {
         let mut logs = Vec::new();
-        let mut core = self.session.core(0)?;
+        let mut core = self.session.core(self.core)?;
 
-        if let Some(channel) = self.rtt.up_channels().first_mut() {
+        for (index, channel) in self.rtt.up_channels().iter_mut().enumerate() {
             let mut buf = [0u8; 1024];
             let bytes_read = channel.read(&mut core, &mut buf)?;
 
             if bytes_read > 0 {
-                self.stream_decoder.received(&buf[..bytes_read]);
-
-                loop {
-                    match self.stream_decoder.decode() {
-                        Ok(frame) => {
-                            let timestamp = frame
-                                .display_timestamp()
-                                .map(|t| t.to_string())
-                                .unwrap_or_default();
-
-                            let raw_level = frame
-                                .level()
-                                .map(|l| l.as_str().to_uppercase())
-                                .unwrap_or_else(|| "UNK".to_string());
-                            let padded_level = format!("{:5}", raw_level);
-
-                            // Apply standard terminal ANSI color codes
-                            let colored_level = match raw_level.as_str() {
-                                "ERROR" => format!("\x1b[31;1m{}\x1b[0m", padded_level), // Bold Red
-                                "WARN" => format!("\x1b[33;1m{}\x1b[0m", padded_level), // Bold Yellow
-                                "INFO" => format!("\x1b[32m{}\x1b[0m", padded_level),   // Green
-                                "DEBUG" => format!("\x1b[36m{}\x1b[0m", padded_level),  // Cyan
-                                "TRACE" => format!("\x1b[90m{}\x1b[0m", padded_level),  // Gray/Dim
-                                _ => padded_level,
-                            };
-
-                            let message = frame.display_message().to_string();
-
-                            let formatted = if timestamp.is_empty() {
-                                format!("[{}] {}", colored_level, message)
-                            } else {
-                                format!("{} [{}] {}", timestamp, colored_level, message)
-                            };
-
-                            logs.push(formatted);
-                        }
-                        // SILENTLY handle partial frames. Do not print "Unexpected EOF" here!
-                        Err(DecodeError::UnexpectedEof) => break,
-                        Err(DecodeError::Malformed) => {
-                            eprintln!("Warning: Malformed defmt frame detected.");
-                            break;
-                        }
-                    }
+                let lines = self.decoders[index].received(&buf[..bytes_read]);
+                if !lines.is_empty() {
+                    logs.push((index, lines));
                 }
             }
         }
 
         Ok(logs)
     }
+
+    /// Writes `data` into the input down-channel, waiting briefly while the
+    /// target's buffer is full.
+    pub fn write_input(&mut self, data: &[u8]) -> io::Result<()> {
+        let mut core = self.session.core(self.core).map_err(io::Error::other)?;
+        let number = self.down;
+        let Some(channel) = self
+            .rtt
+            .down_channels()
+            .iter_mut()
+            .find(|channel| channel.number() == number)
+        else {
+            return Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                format!("The target has no RTT down-channel {}", number),
+            ));
+        };
+
+        let mut rest = data;
+        let deadline = Instant::now() + DOWN_CHANNEL_TIMEOUT;
+        while !rest.is_empty() {
+            let written = channel.write(&mut core, rest).map_err(io::Error::other)?;
+            rest = &rest[written..];
+            if written == 0 {
+                if Instant::now() >= deadline {
+                    return Err(io::Error::new(
+                        io::ErrorKind::TimedOut,
+                        format!(
+                            "RTT down-channel {} is full; is the firmware reading it?",
+                            number
+                        ),
+                    ));
+                }
+                std::thread::sleep(Duration::from_millis(5));
+            }
+        }
+        Ok(())
+    }
+
+    /// Combined counters of the framed and defmt channels, if there are any.
+    fn frame_stats(&self) -> Option<FrameStats> {
+        self.decoders
+            .iter()
+            .filter_map(|decoder| match decoder {
+                ChannelDecoder::Framed(framed) => Some(framed.stats()),
+                ChannelDecoder::Defmt(decoder) => Some(decoder.stats()),
+                _ => None,
+            })
+            .reduce(|total, stats| FrameStats {
+                frames: total.frames + stats.frames,
+                frame_errors: total.frame_errors + stats.frame_errors,
+                decode_errors: total.decode_errors + stats.decode_errors,
+                last_error: stats.last_error.or(total.last_error),
+                ..total
+            })
+    }
+}
+
+/// [`DataSource`](crate::source::DataSource) adapter that (re)attaches an
+/// [`RttDefmtReader`] on demand.
+pub struct RttSource {
+    elf: String,
+    probe: ProbeSetup,
+    setup: ChannelSetup,
+    reader: Option<RttDefmtReader>,
+    /// Lines from channels polled together, handed out one channel per read.
+    pending: VecDeque<ChannelBatch>,
+}
+
+impl RttSource {
+    pub fn new(elf: String, probe: ProbeSetup, setup: ChannelSetup) -> Self {
+        Self {
+            elf,
+            probe,
+            setup,
+            reader: None,
+            pending: VecDeque::new(),
+        }
+    }
+}
+
+impl crate::source::DataSource for RttSource {
+    fn open(&mut self) -> Result<(), crate::source::OpenError> {
+        use crate::source::OpenError;
+
+        if self.elf.is_empty() {
+            return Err(OpenError::Fatal(
+                "RTT mode requires an ELF file. Use --elf <path>".into(),
+            ));
+        }
+
+        match RttDefmtReader::new(&self.elf, &mut self.probe, &self.setup) {
+            Ok(reader) => {
+                self.reader = Some(reader);
+                Ok(())
+            }
+            Err(e) => {
+                let err_msg = e.to_string();
+
+                if err_msg.contains("No such file")
+                    || err_msg.contains("No defmt table")
+                    || err_msg.contains("defmt locations")
+                    || err_msg.contains("ChipNotFound")
+                    || err_msg.contains("Failed to flash")
+                    || err_msg.contains("--core")
+                    || err_msg.contains("--reset-halt")
+                    // Retrying would reset the board again, with the same result
+                    || err_msg.contains("breakpoint at main")
+                    || err_msg.contains("did not reach main")
+                    // Several probes and no --probe; an unplugged one is waited for
+                    || err_msg.contains("Pick one with --probe")
+                {
+                    return Err(OpenError::Fatal(e));
+                }
+                // Otherwise, treat as a transient hardware/connection error and wait
+                Err(OpenError::Unavailable(err_msg))
+            }
+        }
+    }
+
+    fn is_open(&self) -> bool {
+        self.reader.is_some()
+    }
+
+    fn close(&mut self) {
+        self.reader = None;
+        self.pending.clear();
+    }
+
+    fn read(&mut self) -> crate::source::SourceEvent {
+        use crate::source::SourceEvent;
+
+        if let Some((channel, lines)) = self.pending.pop_front() {
+            return SourceEvent::ChannelLines(channel, lines);
+        }
+        let Some(reader) = self.reader.as_mut() else {
+            return SourceEvent::Idle;
+        };
+
+        match reader.poll_logs() {
+            Ok(logs) => {
+                self.pending.extend(logs);
+                match self.pending.pop_front() {
+                    Some((channel, lines)) => SourceEvent::ChannelLines(channel, lines),
+                    None => SourceEvent::Idle,
+                }
+            }
+            Err(e) => {
+                self.reader = None;
+                SourceEvent::Disconnected(e.to_string())
+            }
+        }
+    }
+
+    fn channels(&self) -> Vec<String> {
+        self.reader
+            .as_ref()
+            .map(|reader| reader.names.clone())
+            .unwrap_or_default()
+    }
+
+    fn frame_stats(&self) -> Option<FrameStats> {
+        self.reader.as_ref().and_then(|reader| reader.frame_stats())
+    }
+
+    fn write(&mut self, data: &[u8]) -> io::Result<()> {
+        self.reader
+            .as_mut()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotConnected, "probe is not attached"))?
+            .write_input(data)
+    }
+
+    fn describe(&self) -> String {
+        "RTT_DEBUG_PROBE".to_string()
+    }
 }
@@ -0,0 +1,455 @@
+use crate::source::{DataSource, OpenError, SourceEvent};
+use serde::Deserialize;
+use std::io;
+use std::time::{Duration, Instant};
+
+/// A `--scenario <scenario.toml>` file.
+///
+/// ```toml
+/// seed = 42
+/// interval_ms = 100
+/// format = "Temperature: {temp:.2}, Humidity: {hum:.1}"
+///
+/// [[channel]]
+/// name = "temp"
+/// kind = "sine"
+/// amplitude = 5.0
+/// offset = 21.0
+/// period_s = 10.0
+///
+/// [glitches]
+/// garbage = 0.01
+/// drop = 0.02
+/// ```
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Scenario {
+    /// Seed for noise, random walks and glitches. Same seed, same stream.
+    #[serde(default)]
+    seed: u64,
+    /// Time between emitted lines.
+    #[serde(default = "default_interval_ms")]
+    interval_ms: u64,
+    /// Line template; defaults to `name: {name:.2}` for every channel.
+    format: Option<String>,
+    #[serde(default = "default_line_ending")]
+    line_ending: String,
+    #[serde(rename = "channel", default)]
+    channels: Vec<ChannelSpec>,
+    #[serde(default)]
+    glitches: Glitches,
+}
+
+/// Upper bound for scenario timings; longer ones are surely typos, and would
+/// overflow `Instant` arithmetic.
+const MAX_SCENARIO_MS: u64 = 24 * 60 * 60 * 1000;
+
+fn default_interval_ms() -> u64 {
+    500
+}
+
+fn default_line_ending() -> String {
+    "\r\n".to_string()
+}
+
+#[derive(Debug, Clone, Copy, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+enum Waveform {
+    Sine,
+    Square,
+    Noise,
+    Ramp,
+    RandomWalk,
+    Step,
+}
+
+/// One `[[channel]]` table. Only the fields relevant to `kind` are used.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ChannelSpec {
+    name: String,
+    kind: Waveform,
+    /// How often the value changes; it is held in between. Defaults to every line.
+    rate_ms: Option<u64>,
+    amplitude: Option<f64>,
+    offset: Option<f64>,
+    period_s: Option<f64>,
+    /// Fraction of the period a square wave spends high.
+    duty: Option<f64>,
+    std_dev: Option<f64>,
+    from: Option<f64>,
+    to: Option<f64>,
+    start: Option<f64>,
+    step: Option<f64>,
+    min: Option<f64>,
+    max: Option<f64>,
+    #[serde(default)]
+    values: Vec<f64>,
+    hold_s: Option<f64>,
+}
+
+/// Faults injected into the stream. Probabilities are per emitted line.
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Glitches {
+    #[serde(default)]
+    garbage: f64,
+    garbage_len: Option<usize>,
+    #[serde(default)]
+    drop: f64,
+    /// Cut the line short and lose its line ending.
+    #[serde(default)]
+    truncate: f64,
+    #[serde(default)]
+    disconnect: f64,
+    disconnect_s: Option<f64>,
+}
+
+impl Scenario {
+    pub fn load(path: &str) -> Result<Self, String> {
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read scenario '{}': {}", path, e))?;
+        let scenario: Scenario =
+            toml::from_str(&text).map_err(|e| format!("Invalid scenario '{}': {}", path, e))?;
+
+        if scenario.channels.is_empty() {
+            return Err(format!("Scenario '{}' defines no [[channel]]", path));
+        }
+        if scenario.interval_ms > MAX_SCENARIO_MS {
+            return Err(format!(
+                "Invalid scenario '{}': interval_ms must be at most {} (one day)",
+                path, MAX_SCENARIO_MS
+            ));
+        }
+        if let Some(downtime) = scenario.glitches.disconnect_s
+            && !(0.0..=(MAX_SCENARIO_MS / 1000) as f64).contains(&downtime)
+        {
+            return Err(format!(
+                "Invalid scenario '{}': disconnect_s must be between 0 and {} (one day)",
+                path,
+                MAX_SCENARIO_MS / 1000
+            ));
+        }
+        for channel in &scenario.channels {
+            if channel.rate_ms.is_some_and(|rate| rate > MAX_SCENARIO_MS) {
+                return Err(format!(
+                    "Invalid scenario '{}': rate_ms of channel '{}' must be at most {} (one day)",
+                    path, channel.name, MAX_SCENARIO_MS
+                ));
+            }
+            if channel.kind == Waveform::Step && channel.values.is_empty() {
+                return Err(format!(
+                    "Scenario '{}': step channel '{}' needs `values`",
+                    path, channel.name
+                ));
+            }
+        }
+
+        Ok(scenario)
+    }
+}
+
+// ── Generation ────────────────────────────────────────────────────────────────
+
+/// SplitMix64: tiny, seedable and identical on every platform and release.
+struct Rng(u64);
+
+impl Rng {
+    fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// Uniform in `[0, 1)`.
+    fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    fn chance(&mut self, probability: f64) -> bool {
+        probability > 0.0 && self.next_f64() < probability
+    }
+
+    /// Standard normal sample (Box-Muller).
+    fn gaussian(&mut self) -> f64 {
+        let u1 = self.next_f64().max(f64::MIN_POSITIVE);
+        let u2 = self.next_f64();
+        (-2.0 * u1.ln()).sqrt() * (std::f64::consts::TAU * u2).cos()
+    }
+}
+
+struct Channel {
+    spec: ChannelSpec,
+    rate_s: f64,
+    sample: Option<u64>,
+    value: f64,
+}
+
+impl Channel {
+    /// Value at simulated time `t`, re-sampled once per `rate_ms`.
+    fn value_at(&mut self, t: f64, rng: &mut Rng) -> f64 {
+        let sample = (t / self.rate_s).floor() as u64;
+        if self.sample == Some(sample) {
+            return self.value;
+        }
+
+        let s = &self.spec;
+        let t = sample as f64 * self.rate_s;
+        let amplitude = s.amplitude.unwrap_or(1.0);
+        let offset = s.offset.unwrap_or(0.0);
+        let period = s.period_s.unwrap_or(1.0).max(f64::EPSILON);
+        let phase = (t / period).fract();
+
+        self.value = match s.kind {
+            Waveform::Sine => offset + amplitude * (std::f64::consts::TAU * phase).sin(),
+            Waveform::Square => {
+                let high = phase < s.duty.unwrap_or(0.5);
+                offset + if high { amplitude } else { -amplitude }
+            }
+            Waveform::Noise => offset + s.std_dev.unwrap_or(1.0) * rng.gaussian(),
+            Waveform::Ramp => {
+                let (from, to) = (s.from.unwrap_or(0.0), s.to.unwrap_or(1.0));
+                from + (to - from) * phase
+            }
+            Waveform::RandomWalk => {
+                let current = match self.sample {
+                    Some(_) => self.value,
+                    None => s.start.unwrap_or(0.0),
+                };
+                let step = s.step.unwrap_or(1.0) * (rng.next_f64() * 2.0 - 1.0);
+                (current + step).clamp(
+                    s.min.unwrap_or(f64::NEG_INFINITY),
+                    s.max.unwrap_or(f64::INFINITY),
+                )
+            }
+            Waveform::Step => {
+                let hold = s.hold_s.unwrap_or(1.0).max(f64::EPSILON);
+                s.values[(t / hold) as usize % s.values.len()]
+            }
+        };
+        self.sample = Some(sample);
+        self.value
+    }
+}
+
+enum Segment {
+    Text(String),
+    /// Channel index and optional decimal places.
+    Value(usize, Option<usize>),
+    /// Simulated seconds since start.
+    Time(Option<usize>),
+    /// Line counter.
+    Count,
+}
+
+/// Parses `{name}`, `{name:.2}`, `{t}` and `{n}` placeholders; `{{` and `}}` are literal braces.
+fn parse_template(template: &str, channels: &[Channel]) -> Result<Vec<Segment>, String> {
+    let mut segments = Vec::new();
+    let mut text = String::new();
+    let mut chars = template.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '{' if chars.peek() == Some(&'{') => {
+                chars.next();
+                text.push('{');
+            }
+            '}' if chars.peek() == Some(&'}') => {
+                chars.next();
+                text.push('}');
+            }
+            '{' => {
+                let field: String = chars.by_ref().take_while(|&c| c != '}').collect();
+                let (name, spec) = field.split_once(':').unwrap_or((&field, ""));
+                let precision = match spec.strip_prefix('.') {
+                    Some(digits) => Some(
+                        digits
+                            .parse()
+                            .map_err(|_| format!("bad precision in '{{{}}}'", field))?,
+                    ),
+                    None if spec.is_empty() => None,
+                    None => return Err(format!("unsupported format in '{{{}}}'", field)),
+                };
+
+                if !text.is_empty() {
+                    segments.push(Segment::Text(std::mem::take(&mut text)));
+                }
+                segments.push(match name.trim() {
+                    "t" => Segment::Time(precision),
+                    "n" => Segment::Count,
+                    name => Segment::Value(
+                        channels
+                            .iter()
+                            .position(|c| c.spec.name == name)
+                            .ok_or_else(|| format!("unknown channel '{}' in format", name))?,
+                        precision,
+                    ),
+                });
+            }
+            c => text.push(c),
+        }
+    }
+
+    if !text.is_empty() {
+        segments.push(Segment::Text(text));
+    }
+    Ok(segments)
+}
+
+/// Streams a [`Scenario`] as if it were a device on a serial line.
+pub struct ScenarioSource {
+    channels: Vec<Channel>,
+    template: Vec<Segment>,
+    line_ending: String,
+    glitches: Glitches,
+    interval: Duration,
+    rng: Rng,
+    tick: u64,
+    next_emit: Instant,
+    is_open: bool,
+    /// Set by a disconnect glitch; the device refuses to open until then.
+    offline_until: Option<Instant>,
+    name: String,
+}
+
+impl ScenarioSource {
+    /// `seed_offset` lets several panes run the same scenario without
+    /// producing identical streams.
+    pub fn new(scenario: Scenario, path: &str, seed_offset: u64) -> Result<Self, String> {
+        let interval_ms = scenario.interval_ms.max(1);
+        let channels: Vec<Channel> = scenario
+            .channels
+            .into_iter()
+            .map(|spec| Channel {
+                rate_s: spec.rate_ms.unwrap_or(interval_ms).max(1) as f64 / 1000.0,
+                spec,
+                sample: None,
+                value: 0.0,
+            })
+            .collect();
+
+        let format = scenario.format.unwrap_or_else(|| {
+            channels
+                .iter()
+                .map(|c| format!("{}: {{{}:.2}}", c.spec.name, c.spec.name))
+                .collect::<Vec<_>>()
+                .join(", ")
+        });
+        let template = parse_template(&format, &channels)
+            .map_err(|e| format!("Invalid scenario '{}': {}", path, e))?;
+
+        Ok(Self {
+            channels,
+            template,
+            line_ending: scenario.line_ending,
+            glitches: scenario.glitches,
+            interval: Duration::from_millis(interval_ms),
+            rng: Rng(scenario.seed.wrapping_add(seed_offset)),
+            tick: 0,
+            next_emit: Instant::now(),
+            is_open: false,
+            offline_until: None,
+            name: format!("SIMULATE: {}", path),
+        })
+    }
+
+    fn render_line(&mut self) -> String {
+        let t = self.tick as f64 * self.interval.as_secs_f64();
+        let mut line = String::new();
+
+        for segment in &self.template {
+            match segment {
+                Segment::Text(text) => line.push_str(text),
+                Segment::Value(index, precision) => {
+                    let value = self.channels[*index].value_at(t, &mut self.rng);
+                    push_number(&mut line, value, *precision);
+                }
+                Segment::Time(precision) => push_number(&mut line, t, *precision),
+                Segment::Count => line.push_str(&self.tick.to_string()),
+            }
+        }
+        line
+    }
+}
+
+fn push_number(line: &mut String, value: f64, precision: Option<usize>) {
+    match precision {
+        Some(p) => line.push_str(&format!("{:.*}", p, value)),
+        None => line.push_str(&value.to_string()),
+    }
+}
+
+impl DataSource for ScenarioSource {
+    fn open(&mut self) -> Result<(), OpenError> {
+        if let Some(until) = self.offline_until {
+            if Instant::now() < until {
+                return Err(OpenError::Unavailable(
+                    "Simulated device is unplugged".to_string(),
+                ));
+            }
+            self.offline_until = None;
+        }
+        self.is_open = true;
+        self.next_emit = Instant::now();
+        Ok(())
+    }
+
+    fn is_open(&self) -> bool {
+        self.is_open
+    }
+
+    fn close(&mut self) {
+        self.is_open = false;
+    }
+
+    fn read(&mut self) -> SourceEvent {
+        let now = Instant::now();
+        if !self.is_open || now < self.next_emit {
+            return SourceEvent::Idle;
+        }
+        self.next_emit = now + self.interval;
+
+        // Always render so values stay on the same timeline whether or not
+        // the line survives the glitches below.
+        let mut line = self.render_line();
+        self.tick += 1;
+
+        if self.rng.chance(self.glitches.disconnect) {
+            let downtime = self.glitches.disconnect_s.unwrap_or(2.0);
+            self.offline_until = Some(now + Duration::from_secs_f64(downtime));
+            self.is_open = false;
+            return SourceEvent::Disconnected("Simulated disconnect".to_string());
+        }
+        if self.rng.chance(self.glitches.drop) {
+            return SourceEvent::Idle;
+        }
+
+        let mut out = Vec::new();
+        if self.rng.chance(self.glitches.garbage) {
+            let len = self.glitches.garbage_len.unwrap_or(8);
+            out.extend((0..len).map(|_| self.rng.next_u64() as u8));
+        }
+        if self.rng.chance(self.glitches.truncate) && !line.is_empty() {
+            let mut cut = (self.rng.next_f64() * line.len() as f64) as usize;
+            while !line.is_char_boundary(cut) {
+                cut -= 1;
+            }
+            line.truncate(cut);
+        } else {
+            line.push_str(&self.line_ending);
+        }
+        out.extend_from_slice(line.as_bytes());
+
+        SourceEvent::Bytes(out)
+    }
+
+    fn write(&mut self, _data: &[u8]) -> io::Result<()> {
+        Ok(())
+    }
+
+    fn describe(&self) -> String {
+        self.name.clone()
+    }
+}
@@ -0,0 +1,269 @@
+use serde::Deserialize;
+
+/// A `--schema` file describing framed binary packets as packed C structs.
+///
+/// ```toml
+/// endian = "little"
+///
+/// [id]
+/// type = "u8"                 # every packet starts with a message ID
+///
+/// [[message]]
+/// id = 1
+/// name = "attitude"
+/// fields = [
+///   { name = "Pitch", type = "i16", scale = 0.01 },
+///   { name = "Roll", type = "i16", scale = 0.01 },
+///   { name = "Yaw", type = "i16", scale = 0.01 },
+///   { type = "pad", size = 2 },
+///   { type = "u8", bits = [{ name = "armed", bit = 0 }, { name = "mode", bit = 1, width = 3 }] },
+/// ]
+/// ```
+///
+/// Fields are packed one after another, starting right after the ID, unless
+/// they give a byte offset with `at`.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Schema {
+    #[serde(default)]
+    endian: Endian,
+    id: Option<IdSpec>,
+    #[serde(rename = "message", default)]
+    messages: Vec<Message>,
+}
+
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum Endian {
+    #[default]
+    Little,
+    Big,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+enum FieldType {
+    U8,
+    I8,
+    U16,
+    I16,
+    U32,
+    I32,
+    U64,
+    I64,
+    F32,
+    F64,
+    /// Skipped bytes; needs `size`.
+    Pad,
+}
+
+/// Where the message ID sits in each packet.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct IdSpec {
+    #[serde(rename = "type")]
+    kind: FieldType,
+    #[serde(default)]
+    at: usize,
+    endian: Option<Endian>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Message {
+    id: Option<u64>,
+    name: Option<String>,
+    fields: Vec<Field>,
+    /// Byte offset of each field, resolved when the schema is loaded.
+    #[serde(skip)]
+    positions: Vec<usize>,
+    /// Shortest payload that holds every field.
+    #[serde(skip)]
+    min_len: usize,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Field {
+    /// Reading name for the whole field; optional when only `bits` are wanted.
+    name: Option<String>,
+    #[serde(rename = "type")]
+    kind: FieldType,
+    at: Option<usize>,
+    size: Option<usize>,
+    endian: Option<Endian>,
+    #[serde(default = "default_scale")]
+    scale: f64,
+    #[serde(default)]
+    offset: f64,
+    #[serde(default)]
+    bits: Vec<BitField>,
+}
+
+fn default_scale() -> f64 {
+    1.0
+}
+
+/// A value packed into some bits of an integer field, counted from the LSB.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct BitField {
+    name: String,
+    bit: u32,
+    #[serde(default = "default_width")]
+    width: u32,
+}
+
+fn default_width() -> u32 {
+    1
+}
+
+impl FieldType {
+    fn size(self) -> usize {
+        match self {
+            FieldType::U8 | FieldType::I8 => 1,
+            FieldType::U16 | FieldType::I16 => 2,
+            FieldType::U32 | FieldType::I32 | FieldType::F32 => 4,
+            FieldType::U64 | FieldType::I64 | FieldType::F64 => 8,
+            FieldType::Pad => 0,
+        }
+    }
+
+    fn is_integer(self) -> bool {
+        !matches!(self, FieldType::F32 | FieldType::F64 | FieldType::Pad)
+    }
+
+    /// Interprets the raw bits read from the packet as this type.
+    fn value(self, raw: u64) -> f64 {
+        match self {
+            FieldType::I8 => raw as u8 as i8 as f64,
+            FieldType::I16 => raw as u16 as i16 as f64,
+            FieldType::I32 => raw as u32 as i32 as f64,
+            FieldType::I64 => raw as i64 as f64,
+            FieldType::F32 => f32::from_bits(raw as u32) as f64,
+            FieldType::F64 => f64::from_bits(raw),
+            _ => raw as f64,
+        }
+    }
+}
+
+fn read_raw(bytes: &[u8], endian: Endian) -> u64 {
+    let fold = |acc: u64, b: &u8| (acc << 8) | *b as u64;
+    match endian {
+        Endian::Big => bytes.iter().fold(0, fold),
+        Endian::Little => bytes.iter().rev().fold(0, fold),
+    }
+}
+
+impl Schema {
+    pub fn load(path: &str) -> Result<Self, String> {
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read schema '{}': {}", path, e))?;
+        let mut schema: Schema =
+            toml::from_str(&text).map_err(|e| format!("Invalid schema '{}': {}", path, e))?;
+        let invalid = |msg: String| format!("Invalid schema '{}': {}", path, msg);
+
+        if schema.messages.is_empty() {
+            return Err(invalid("no [[message]] defined".to_string()));
+        }
+        let start = match schema.id {
+            Some(ref id) if !id.kind.is_integer() => {
+                return Err(invalid(
+                    "the message ID must be an integer type".to_string(),
+                ));
+            }
+            Some(ref id) => id.at + id.kind.size(),
+            None if schema.messages.len() > 1 => {
+                return Err(invalid(
+                    "several [[message]] tables need an [id] to tell them apart".to_string(),
+                ));
+            }
+            None => 0,
+        };
+
+        for (index, message) in schema.messages.iter_mut().enumerate() {
+            let label = message
+                .name
+                .clone()
+                .unwrap_or_else(|| format!("message {}", index + 1));
+            if schema.id.is_some() && message.id.is_none() {
+                return Err(invalid(format!("{} has no id", label)));
+            }
+
+            let mut pos = start;
+            for field in &message.fields {
+                let size = match (field.kind, field.size) {
+                    (FieldType::Pad, Some(size)) => size,
+                    (FieldType::Pad, None) => {
+                        return Err(invalid(format!("{}: pad fields need a size", label)));
+                    }
+                    (kind, _) => kind.size(),
+                };
+                if field.kind != FieldType::Pad && field.name.is_none() && field.bits.is_empty() {
+                    return Err(invalid(format!("{}: fields need a name or bits", label)));
+                }
+                if !field.bits.is_empty() && !field.kind.is_integer() {
+                    return Err(invalid(format!(
+                        "{}: bits are only allowed on integer fields",
+                        label
+                    )));
+                }
+                for bits in &field.bits {
+                    if bits.width == 0 || bits.bit + bits.width > size as u32 * 8 {
+                        return Err(invalid(format!(
+                            "{}: bits '{}' do not fit in a {}-byte field",
+                            label, bits.name, size
+                        )));
+                    }
+                }
+
+                let at = field.at.unwrap_or(pos);
+                message.positions.push(at);
+                message.min_len = message.min_len.max(at + size);
+                pos = at + size;
+            }
+        }
+
+        Ok(schema)
+    }
+
+    /// Decodes one packet into the same `(name, value)` pairs that
+    /// `parser::parse_sensor_data` produces for text.
+    pub fn decode(&self, payload: &[u8]) -> Result<Vec<(String, f64)>, &'static str> {
+        let message = match self.id {
+            None => &self.messages[0],
+            Some(ref id) => {
+                let bytes = payload
+                    .get(id.at..id.at + id.kind.size())
+                    .ok_or("packet too short for the message ID")?;
+                let id = read_raw(bytes, id.endian.unwrap_or(self.endian));
+                self.messages
+                    .iter()
+                    .find(|m| m.id == Some(id))
+                    .ok_or("unknown message ID")?
+            }
+        };
+        if payload.len() < message.min_len {
+            return Err("packet shorter than its message");
+        }
+
+        let mut readings = Vec::new();
+        for (field, &at) in message.fields.iter().zip(&message.positions) {
+            if field.kind == FieldType::Pad {
+                continue;
+            }
+            let bytes = &payload[at..at + field.kind.size()];
+            let raw = read_raw(bytes, field.endian.unwrap_or(self.endian));
+
+            if let Some(ref name) = field.name {
+                let value = field.kind.value(raw) * field.scale + field.offset;
+                readings.push((name.clone(), value));
+            }
+            for bits in &field.bits {
+                let mask = u64::MAX >> (64 - bits.width);
+                readings.push((bits.name.clone(), ((raw >> bits.bit) & mask) as f64));
+            }
+        }
+        Ok(readings)
+    }
+}
@@ -0,0 +1,214 @@
+use crate::parser::parse_sensor_data;
+use crate::serial::get_timestamp;
+use crate::source::DataSource;
+use rhai::{AST, CallFnOptions, Dynamic, Engine, Map, Scope};
+use std::cell::RefCell;
+use std::error::Error;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::rc::Rc;
+
+/// Upper bound on Rhai operations per call, so a runaway loop in a hook
+/// reports an error instead of freezing the UI.
+const MAX_OPERATIONS: u64 = 1_000_000;
+
+/// Side effects requested by the script during one call.
+#[derive(Default)]
+struct Effects {
+    readings: Vec<(String, f64)>,
+    writes: Vec<String>,
+    logs: Vec<String>,
+    alerts: Vec<String>,
+}
+
+/// What to do with a line after the script has seen it.
+#[derive(Default)]
+pub struct ScriptOutput {
+    /// The line to display, log and parse; `None` when the script dropped it.
+    pub line: Option<String>,
+    /// Readings parsed from `line` followed by any the script emitted.
+    pub readings: Vec<(String, f64)>,
+    /// Text the script wants sent to the device, one line each.
+    pub writes: Vec<String>,
+    pub logs: Vec<String>,
+    pub alerts: Vec<String>,
+}
+
+/// A `--script` file whose `on_line` / `on_send` functions see every line.
+///
+/// Each hook may return a string to replace the line, `false` to drop it, or
+/// nothing to keep it unchanged. `this` is a map that persists between calls.
+pub struct LineScript {
+    engine: Engine,
+    ast: AST,
+    scope: Scope<'static>,
+    state: Dynamic,
+    effects: Rc<RefCell<Effects>>,
+    has_on_line: bool,
+    has_on_send: bool,
+}
+
+impl LineScript {
+    pub fn load(path: &str) -> Result<Self, Box<dyn Error>> {
+        let effects = Rc::new(RefCell::new(Effects::default()));
+        let mut engine = Engine::new();
+        engine.set_max_operations(MAX_OPERATIONS);
+
+        let fx = effects.clone();
+        engine.register_fn("emit", move |name: &str, value: f64| {
+            fx.borrow_mut().readings.push((name.to_string(), value));
+        });
+        let fx = effects.clone();
+        engine.register_fn("emit", move |name: &str, value: i64| {
+            fx.borrow_mut()
+                .readings
+                .push((name.to_string(), value as f64));
+        });
+        let fx = effects.clone();
+        engine.register_fn("write", move |text: &str| {
+            fx.borrow_mut().writes.push(text.to_string());
+        });
+        let fx = effects.clone();
+        engine.register_fn("log", move |msg: &str| {
+            fx.borrow_mut().logs.push(msg.to_string());
+        });
+        let fx = effects.clone();
+        engine.register_fn("alert", move |msg: &str| {
+            fx.borrow_mut().alerts.push(msg.to_string());
+        });
+        engine.register_fn("parse", |line: &str| {
+            parse_sensor_data(line)
+                .into_iter()
+                .map(|(name, value)| (name.into(), Dynamic::from(value)))
+                .collect::<Map>()
+        });
+
+        // `print` would scribble over the TUI, so it goes to the log as well
+        let fx = effects.clone();
+        engine.on_print(move |msg| fx.borrow_mut().logs.push(msg.to_string()));
+        let fx = effects.clone();
+        engine.on_debug(move |msg, _, _| fx.borrow_mut().logs.push(msg.to_string()));
+
+        let ast = engine
+            .compile_file(path.into())
+            .map_err(|e| format!("Failed to load script {}: {}", path, e))?;
+        let has_fn = |name: &str| {
+            ast.iter_functions()
+                .any(|f| f.name == name && f.params.len() == 1)
+        };
+        let has_on_line = has_fn("on_line");
+        let has_on_send = has_fn("on_send");
+
+        // Top-level statements run once, e.g. to set up constants
+        let mut scope = Scope::new();
+        engine
+            .run_ast_with_scope(&mut scope, &ast)
+            .map_err(|e| format!("Script {} failed: {}", path, e))?;
+
+        Ok(Self {
+            engine,
+            ast,
+            scope,
+            state: Dynamic::from_map(Map::new()),
+            effects,
+            has_on_line,
+            has_on_send,
+        })
+    }
+
+    /// Runs `on_line` for a line received from the device.
+    pub fn on_line(&mut self, line: &str) -> ScriptOutput {
+        let mut output = self.call("on_line", self.has_on_line, line);
+        if let Some(ref kept) = output.line {
+            let mut readings = parse_sensor_data(kept);
+            readings.append(&mut output.readings);
+            output.readings = readings;
+        }
+        output
+    }
+
+    /// Runs `on_send` for a line about to be sent to the device. Only
+    /// emitted readings are reported; the sent text is not parsed.
+    pub fn on_send(&mut self, line: &str) -> ScriptOutput {
+        self.call("on_send", self.has_on_send, line)
+    }
+
+    fn call(&mut self, name: &str, defined: bool, line: &str) -> ScriptOutput {
+        let mut output = ScriptOutput {
+            line: Some(line.to_string()),
+            ..Default::default()
+        };
+        if !defined {
+            return output;
+        }
+
+        let options = CallFnOptions::new()
+            .eval_ast(false)
+            .bind_this_ptr(&mut self.state);
+        let result = self.engine.call_fn_with_options::<Dynamic>(
+            options,
+            &mut self.scope,
+            &self.ast,
+            name,
+            (line.to_string(),),
+        );
+
+        let effects = std::mem::take(&mut *self.effects.borrow_mut());
+        output.readings = effects.readings;
+        output.writes = effects.writes;
+        output.logs = effects.logs;
+        output.alerts = effects.alerts;
+
+        match result {
+            Ok(value) if value.is_string() => output.line = value.into_string().ok(),
+            Ok(value) if value.as_bool() == Ok(false) => output.line = None,
+            Ok(_) => {}
+            // A broken hook must not swallow data; keep the line and say why
+            Err(e) => output.alerts.push(format!("{} failed: {}", name, e)),
+        }
+        output
+    }
+}
+
+/// Carries out the side effects a hook asked for: `log` and `alert` messages
+/// go to `--log` and `write` calls go to the device. Returns the message the
+/// UI should highlight, which is the last alert or why a write failed.
+pub fn apply_effects(
+    output: &ScriptOutput,
+    source: &mut dyn DataSource,
+    log_writer: &mut Option<BufWriter<File>>,
+) -> Option<String> {
+    let mut highlight = log_effects(output, log_writer);
+    for text in &output.writes {
+        match source.write(format!("{}\r", text).as_bytes()) {
+            Ok(()) => {
+                if let Some(writer) = log_writer {
+                    writeln!(writer, "TX [{}]: {}", get_timestamp(), text).ok();
+                }
+            }
+            Err(e) => highlight = Some(format!("Script write failed: {}", e)),
+        }
+    }
+    if let Some(writer) = log_writer {
+        let _ = writer.flush();
+    }
+    highlight
+}
+
+/// Writes a hook's `log` and `alert` messages to `--log`, for views that send
+/// its `write` calls themselves. Returns the last alert.
+pub fn log_effects(
+    output: &ScriptOutput,
+    log_writer: &mut Option<BufWriter<File>>,
+) -> Option<String> {
+    if let Some(writer) = log_writer {
+        for msg in &output.logs {
+            writeln!(writer, "SCRIPT [{}]: {}", get_timestamp(), msg).ok();
+        }
+        for msg in &output.alerts {
+            writeln!(writer, "ALERT [{}]: {}", get_timestamp(), msg).ok();
+        }
+        let _ = writer.flush();
+    }
+    output.alerts.last().cloned()
+}
@@ -0,0 +1,202 @@
+use crate::config::ServeWritePolicy;
+use crate::source::{DataSource, OpenError, SourceEvent};
+use std::io::{self, Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+
+/// Bytes queued for a client that is not keeping up. Past this the client
+/// is dropped rather than stalling the device or the other clients.
+const MAX_CLIENT_BACKLOG: usize = 256 * 1024;
+
+struct ServeClient {
+    stream: TcpStream,
+    peer: SocketAddr,
+    outbox: Vec<u8>,
+}
+
+/// Wraps any [`DataSource`] and mirrors everything it receives to TCP
+/// clients, turning ComChan into a shared-port server (like ser2net).
+///
+/// The device is still opened and shown locally exactly as without
+/// `--serve`; client writes are forwarded according to [`ServeWritePolicy`].
+pub struct ServedSource {
+    inner: Box<dyn DataSource>,
+    listener: TcpListener,
+    address: String,
+    policy: ServeWritePolicy,
+    clients: Vec<ServeClient>,
+    buffer: [u8; 1024],
+}
+
+impl ServedSource {
+    pub fn bind(
+        inner: Box<dyn DataSource>,
+        address: &str,
+        policy: ServeWritePolicy,
+    ) -> io::Result<Self> {
+        let listener = TcpListener::bind(address)?;
+        listener.set_nonblocking(true)?;
+
+        Ok(Self {
+            inner,
+            address: listener
+                .local_addr()
+                .map(|a| a.to_string())
+                .unwrap_or_else(|_| address.to_string()),
+            listener,
+            policy,
+            clients: Vec::new(),
+            buffer: [0u8; 1024],
+        })
+    }
+
+    pub fn address(&self) -> &str {
+        &self.address
+    }
+
+    fn accept_clients(&mut self) {
+        while let Ok((stream, peer)) = self.listener.accept() {
+            if stream.set_nonblocking(true).is_err() {
+                continue;
+            }
+            let _ = stream.set_nodelay(true);
+            self.clients.push(ServeClient {
+                stream,
+                peer,
+                outbox: Vec::new(),
+            });
+        }
+    }
+
+    /// Reads client input, forwards what the policy allows and flushes
+    /// pending output. Clients that hang up or fall too far behind are dropped.
+    fn pump_clients(&mut self) {
+        let mut index = 0;
+        while index < self.clients.len() {
+            let may_write = match self.policy {
+                ServeWritePolicy::All => true,
+                // Clients are kept in connection order, so the owner is always first
+                ServeWritePolicy::First => index == 0,
+                ServeWritePolicy::None => false,
+            };
+
+            let mut alive = true;
+            loop {
+                match self.clients[index].stream.read(&mut self.buffer) {
+                    Ok(0) => {
+                        alive = false;
+                        break;
+                    }
+                    Ok(n) => {
+                        if may_write && self.inner.is_open() {
+                            let _ = self.inner.write(&self.buffer[..n]);
+                        }
+                    }
+                    Err(ref e) if e.kind() == io::ErrorKind::WouldBlock => break,
+                    Err(ref e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                    Err(_) => {
+                        alive = false;
+                        break;
+                    }
+                }
+            }
+
+            let client = &mut self.clients[index];
+            while alive && !client.outbox.is_empty() {
+                match client.stream.write(&client.outbox) {
+                    Ok(0) => alive = false,
+                    Ok(n) => {
+                        client.outbox.drain(..n);
+                    }
+                    Err(ref e) if e.kind() == io::ErrorKind::WouldBlock => break,
+                    Err(ref e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                    Err(_) => alive = false,
+                }
+            }
+
+            if alive && client.outbox.len() <= MAX_CLIENT_BACKLOG {
+                index += 1;
+            } else {
+                self.clients.remove(index);
+            }
+        }
+    }
+
+    fn broadcast(&mut self, data: &[u8]) {
+        for client in &mut self.clients {
+            client.outbox.extend_from_slice(data);
+        }
+    }
+}
+
+impl DataSource for ServedSource {
+    fn open(&mut self) -> Result<(), OpenError> {
+        // Keep accepting clients while waiting for the device to come back
+        self.accept_clients();
+        self.pump_clients();
+        self.inner.open()
+    }
+
+    fn is_open(&self) -> bool {
+        self.inner.is_open()
+    }
+
+    fn close(&mut self) {
+        self.inner.close();
+    }
+
+    fn read(&mut self) -> SourceEvent {
+        self.accept_clients();
+
+        let event = self.inner.read();
+        match &event {
+            SourceEvent::Bytes(raw) => self.broadcast(raw),
+            SourceEvent::Lines(lines) | SourceEvent::ChannelLines(_, lines) => {
+                for line in lines {
+                    self.broadcast(format!("{}\r\n", line).as_bytes());
+                }
+            }
+            _ => {}
+        }
+
+        self.pump_clients();
+        event
+    }
+
+    fn write(&mut self, data: &[u8]) -> io::Result<()> {
+        self.inner.write(data)
+    }
+
+    fn set_dtr(&mut self, level: bool) -> io::Result<()> {
+        self.inner.set_dtr(level)
+    }
+
+    fn frame_stats(&self) -> Option<crate::framing::FrameStats> {
+        self.inner.frame_stats()
+    }
+
+    fn channels(&self) -> Vec<String> {
+        self.inner.channels()
+    }
+
+    fn is_serial(&self) -> bool {
+        self.inner.is_serial()
+    }
+
+    fn describe(&self) -> String {
+        let peers = self
+            .clients
+            .iter()
+            .map(|c| c.peer.to_string())
+            .collect::<Vec<_>>();
+        if peers.is_empty() {
+            format!("{} (serving on {})", self.inner.describe(), self.address)
+        } else {
+            format!(
+                "{} (serving on {}: {})",
+                self.inner.describe(),
+                self.address,
+                peers.join(", ")
+            )
+        }
+    }
+}
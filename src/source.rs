@@ -0,0 +1,425 @@
+use crate::config::MergedConfig;
+use crate::serial::{parse_data_bits, parse_flow_control, parse_parity, parse_stop_bits};
+use serialport::{DataBits, FlowControl, Parity, StopBits};
+use std::error::Error;
+use std::fmt;
+use std::io::{self, Read, Write};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Result of a single non-blocking poll of a [`DataSource`].
+pub enum SourceEvent {
+    /// Raw bytes from a byte-stream transport (serial, simulator, BLE).
+    Bytes(Vec<u8>),
+    /// Lines that are already framed and formatted (RTT/defmt, replayed sessions).
+    Lines(Vec<String>),
+    /// Lines from one of several named channels (multi-channel RTT); the index
+    /// refers to [`DataSource::channels`].
+    ChannelLines(usize, Vec<String>),
+    /// Nothing available right now.
+    Idle,
+    /// The link dropped. The source is closed and must be re-opened.
+    Disconnected(String),
+    /// The source is exhausted and will never produce data again.
+    Eof,
+}
+
+/// Why a [`DataSource`] could not be opened.
+pub enum OpenError {
+    /// Bad configuration or firmware; retrying will not help.
+    Fatal(Box<dyn Error>),
+    /// The device is not reachable yet; the caller should wait and retry.
+    Unavailable(String),
+}
+
+impl fmt::Display for OpenError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OpenError::Fatal(e) => write!(f, "{}", e),
+            OpenError::Unavailable(msg) => write!(f, "{}", msg),
+        }
+    }
+}
+
+/// A transport that the monitor, plotter and dual view can all consume.
+///
+/// Sources are created closed. UIs call [`reconnect`](DataSource::reconnect)
+/// until it succeeds, poll [`read`](DataSource::read) every frame and go back
+/// to reconnecting whenever the source reports [`SourceEvent::Disconnected`].
+pub trait DataSource {
+    fn open(&mut self) -> Result<(), OpenError>;
+    fn is_open(&self) -> bool;
+    fn close(&mut self);
+    fn read(&mut self) -> SourceEvent;
+    fn write(&mut self, data: &[u8]) -> io::Result<()>;
+
+    /// Drives the DTR modem line, which resets boards with an auto-reset circuit.
+    fn set_dtr(&mut self, _level: bool) -> io::Result<()> {
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            format!("{} has no DTR line", self.describe()),
+        ))
+    }
+
+    /// Packet counters when `--framing` or defmt splits this source into frames.
+    fn frame_stats(&self) -> Option<crate::framing::FrameStats> {
+        None
+    }
+
+    /// Names of the channels reported through [`SourceEvent::ChannelLines`].
+    fn channels(&self) -> Vec<String> {
+        Vec::new()
+    }
+
+    /// A serial line, local or bridged over the network, whose bytes are
+    /// echoed untouched. Other byte sources may end lines with a bare `\n`.
+    fn is_serial(&self) -> bool {
+        false
+    }
+
+    /// Drops whatever is left of the old link and opens the source again.
+    /// Also used for the first connection, since closing a closed source is a no-op.
+    fn reconnect(&mut self) -> Result<(), OpenError> {
+        self.close();
+        self.open()
+    }
+
+    /// Short human readable name used in banners and status bars.
+    fn describe(&self) -> String;
+}
+
+/// Builds the source selected by the merged configuration.
+///
+/// `port_name` selects between a local serial device, a raw `tcp://host:port`
+/// bridge, an `rfc2217://host:port` terminal server and stdin (`-`); the other
+/// modes ignore it. `sim_profile` and `serial_open` let each UI keep its own
+/// fake data and serial connect sequence.
+pub fn from_config(
+    config: &MergedConfig,
+    port_name: &str,
+    sim_profile: SimProfile,
+    serial_open: SerialOpen,
+) -> Result<Box<dyn DataSource>, Box<dyn Error>> {
+    if let Some(ref path) = config.replay_file {
+        return Ok(Box::new(crate::replay::ReplaySource::new(path)));
+    }
+
+    if config.rtt {
+        // Channel options are checked before the user is asked to pick a probe
+        let channels = crate::rtt_reader::ChannelSetup::from_config(config)?;
+        return Ok(Box::new(crate::rtt_reader::RttSource::new(
+            config.elf.clone().unwrap_or_default(),
+            crate::rtt_reader::ProbeSetup::from_config(config)?,
+            channels,
+        )));
+    }
+
+    #[cfg(feature = "ble")]
+    if config.ble {
+        return Ok(Box::new(crate::ble::BleSource::start(config)?));
+    }
+
+    if config.simulate {
+        if let Some(ref path) = config.scenario {
+            let seed_offset = match sim_profile {
+                SimProfile::Packets(pane) => pane as u64,
+                _ => 0,
+            };
+            let scenario = crate::scenario::Scenario::load(path)?;
+            return Ok(Box::new(crate::scenario::ScenarioSource::new(
+                scenario,
+                path,
+                seed_offset,
+            )?));
+        }
+        return Ok(Box::new(SimulatedSource::new(sim_profile)));
+    }
+
+    if port_name == crate::pipe::STDIN_PORT {
+        return Ok(Box::new(crate::pipe::StdinSource::new()));
+    }
+
+    if let Some(address) = port_name.strip_prefix(crate::rfc2217::RFC2217_SCHEME) {
+        return Ok(Box::new(crate::rfc2217::Rfc2217Source::new(
+            config, address,
+        )?));
+    }
+
+    if let Some(address) = port_name.strip_prefix(crate::tcp::TCP_SCHEME) {
+        return Ok(Box::new(crate::tcp::TcpSource::new(
+            address,
+            config.timeout_ms,
+        )));
+    }
+
+    Ok(Box::new(SerialSource::new(config, port_name, serial_open)?))
+}
+
+// ── Serial ────────────────────────────────────────────────────────────────────
+
+/// What [`SerialSource`] does to the line right after opening it.
+#[derive(Clone, Copy)]
+pub struct SerialOpen {
+    /// Level DTR is driven to, or `None` to leave it as the driver set it.
+    pub dtr: Option<bool>,
+    /// Wait `--reset-delay` so a board that was just reset can boot.
+    pub settle: bool,
+    /// Send a `\r` so a remote shell prints its prompt.
+    pub wake: bool,
+    /// Drop whatever arrived before the port is handed to the UI.
+    pub flush_input: bool,
+}
+
+impl SerialOpen {
+    /// Pulses DTR low so auto-reset boards start from a clean boot, then
+    /// nudges the shell.
+    pub const MONITOR: Self = Self {
+        dtr: Some(false),
+        settle: true,
+        wake: true,
+        flush_input: false,
+    };
+
+    /// Leaves the board running and discards stale input so it is not plotted.
+    pub const PLOTTER: Self = Self {
+        dtr: None,
+        settle: true,
+        wake: false,
+        flush_input: true,
+    };
+
+    /// Holds DTR high and starts reading straight away.
+    pub const DUAL: Self = Self {
+        dtr: Some(true),
+        settle: false,
+        wake: false,
+        flush_input: false,
+    };
+}
+
+pub struct SerialSource {
+    port_name: String,
+    baud: u32,
+    timeout: Duration,
+    reset_delay: Duration,
+    data_bits: DataBits,
+    stop_bits: StopBits,
+    parity: Parity,
+    flow_control: FlowControl,
+    open_sequence: SerialOpen,
+    port: Option<Box<dyn serialport::SerialPort>>,
+    buffer: [u8; 1024],
+}
+
+impl SerialSource {
+    pub fn new(
+        config: &MergedConfig,
+        port_name: &str,
+        open_sequence: SerialOpen,
+    ) -> Result<Self, String> {
+        Ok(Self {
+            port_name: port_name.to_string(),
+            baud: config.baud,
+            timeout: Duration::from_millis(config.timeout_ms),
+            reset_delay: Duration::from_millis(config.reset_delay_ms),
+            data_bits: parse_data_bits(config.data_bits)
+                .map_err(|e| format!("Configuration error: {}", e))?,
+            stop_bits: parse_stop_bits(config.stop_bits)
+                .map_err(|e| format!("Configuration error: {}", e))?,
+            parity: parse_parity(&config.parity)
+                .map_err(|e| format!("Configuration error: {}", e))?,
+            flow_control: parse_flow_control(&config.flow_control)
+                .map_err(|e| format!("Configuration error: {}", e))?,
+            open_sequence,
+            port: None,
+            buffer: [0u8; 1024],
+        })
+    }
+}
+
+impl DataSource for SerialSource {
+    fn open(&mut self) -> Result<(), OpenError> {
+        let mut port = serialport::new(&self.port_name, self.baud)
+            .timeout(self.timeout)
+            .data_bits(self.data_bits)
+            .stop_bits(self.stop_bits)
+            .parity(self.parity)
+            .flow_control(self.flow_control)
+            .open()
+            .map_err(|e| OpenError::Unavailable(e.to_string()))?;
+
+        let sequence = self.open_sequence;
+        if let Some(level) = sequence.dtr {
+            let _ = port.write_data_terminal_ready(level);
+        }
+        if sequence.settle {
+            thread::sleep(self.reset_delay);
+        }
+        if sequence.flush_input {
+            let _ = port.clear(serialport::ClearBuffer::Input);
+        }
+        if sequence.wake {
+            let _ = port.write_all(b"\r");
+            let _ = port.flush();
+        }
+
+        self.port = Some(port);
+        Ok(())
+    }
+
+    fn is_open(&self) -> bool {
+        self.port.is_some()
+    }
+
+    fn close(&mut self) {
+        self.port = None;
+    }
+
+    fn read(&mut self) -> SourceEvent {
+        let Some(port) = self.port.as_mut() else {
+            return SourceEvent::Idle;
+        };
+
+        let result = match port.bytes_to_read() {
+            Ok(0) => return SourceEvent::Idle,
+            Ok(_) => port.read(&mut self.buffer),
+            Err(e) => Err(e.into()),
+        };
+
+        match result {
+            Ok(n) if n > 0 => SourceEvent::Bytes(self.buffer[..n].to_vec()),
+            Ok(_) => SourceEvent::Idle,
+            Err(ref e) if e.kind() == io::ErrorKind::TimedOut => SourceEvent::Idle,
+            Err(e) => {
+                self.port = None;
+                SourceEvent::Disconnected(e.to_string())
+            }
+        }
+    }
+
+    fn write(&mut self, data: &[u8]) -> io::Result<()> {
+        let port = self
+            .port
+            .as_mut()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotConnected, "port is not open"))?;
+        port.write_all(data)?;
+        port.flush()
+    }
+
+    fn set_dtr(&mut self, level: bool) -> io::Result<()> {
+        let port = self
+            .port
+            .as_mut()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotConnected, "port is not open"))?;
+        port.write_data_terminal_ready(level)
+            .map_err(io::Error::from)
+    }
+
+    fn is_serial(&self) -> bool {
+        true
+    }
+
+    fn describe(&self) -> String {
+        format!("{} @ {} baud", self.port_name, self.baud)
+    }
+}
+
+// ── Simulator ─────────────────────────────────────────────────────────────────
+
+/// Which fake data stream the simulator produces.
+#[derive(Clone, Copy)]
+pub enum SimProfile {
+    /// Temperature / Humidity / Pressure readings, twice a second.
+    Environment,
+    /// Pitch / Roll / Yaw angles at 20 Hz for the 3D view.
+    Imu,
+    /// Numbered packets for one pane of the dual view.
+    Packets(u8),
+}
+
+pub struct SimulatedSource {
+    profile: SimProfile,
+    interval: Duration,
+    next_emit: Instant,
+    tick: u64,
+    is_open: bool,
+}
+
+impl SimulatedSource {
+    pub fn new(profile: SimProfile) -> Self {
+        let interval = match profile {
+            SimProfile::Imu => Duration::from_millis(50),
+            SimProfile::Environment | SimProfile::Packets(_) => Duration::from_millis(500),
+        };
+
+        Self {
+            profile,
+            interval,
+            next_emit: Instant::now(),
+            tick: 0,
+            is_open: false,
+        }
+    }
+
+    fn next_line(&self) -> String {
+        match self.profile {
+            SimProfile::Environment => {
+                let t = std::time::SystemTime::now()
+                    .duration_since(std::time::UNIX_EPOCH)
+                    .unwrap_or_default()
+                    .as_secs_f64();
+                format!(
+                    "Temperature: {:.2}, Humidity: {:.2}, Pressure: {:.2}\r\n",
+                    (t * 1.0).sin() * 50.0,
+                    (t * 0.8).cos() * 50.0,
+                    (t * 0.5).sin() * 50.0
+                )
+            }
+            SimProfile::Imu => {
+                let t = self.tick as f64 * 0.3;
+                format!(
+                    "Pitch: {:.2}, Roll: {:.2}, Yaw: {:.2}\r\n",
+                    (t * 0.5).sin() * 45.0,
+                    (t * 0.8).cos() * 30.0,
+                    (t * 2.0) % 360.0
+                )
+            }
+            SimProfile::Packets(port) => format!("SIM [Port {}]: Packet {}\n", port, self.tick),
+        }
+    }
+}
+
+impl DataSource for SimulatedSource {
+    fn open(&mut self) -> Result<(), OpenError> {
+        self.is_open = true;
+        Ok(())
+    }
+
+    fn is_open(&self) -> bool {
+        self.is_open
+    }
+
+    fn close(&mut self) {
+        self.is_open = false;
+    }
+
+    fn read(&mut self) -> SourceEvent {
+        let now = Instant::now();
+        if !self.is_open || now < self.next_emit {
+            return SourceEvent::Idle;
+        }
+
+        let line = self.next_line();
+        self.next_emit = now + self.interval;
+        self.tick += 1;
+        SourceEvent::Bytes(line.into_bytes())
+    }
+
+    fn write(&mut self, _data: &[u8]) -> io::Result<()> {
+        Ok(())
+    }
+
+    fn describe(&self) -> String {
+        "SIMULATE_PORT".to_string()
+    }
+}
@@ -0,0 +1,120 @@
+use crate::source::{DataSource, OpenError, SourceEvent};
+use std::io::{self, Read, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::time::Duration;
+
+pub const TCP_SCHEME: &str = "tcp://";
+
+/// Raw TCP client for ser2net / ESP-link style bridges that forward the
+/// UART byte stream unchanged.
+pub struct TcpSource {
+    address: String,
+    timeout: Duration,
+    stream: Option<TcpStream>,
+    buffer: [u8; 1024],
+}
+
+impl TcpSource {
+    /// `address` is the `host:port` part of a `tcp://host:port` URL.
+    pub fn new(address: &str, timeout_ms: u64) -> Self {
+        Self {
+            address: address.to_string(),
+            timeout: Duration::from_millis(timeout_ms.max(1)),
+            stream: None,
+            buffer: [0u8; 1024],
+        }
+    }
+}
+
+/// Resolves `host:port` and connects to the first address that answers.
+pub fn connect(address: &str, timeout: Duration) -> Result<TcpStream, OpenError> {
+    let addrs = address.to_socket_addrs().map_err(|e| {
+        if e.kind() == io::ErrorKind::InvalidInput {
+            OpenError::Fatal(format!("Invalid TCP address '{}': {}", address, e).into())
+        } else {
+            // A bridge that is rebooting can drop out of DNS/mDNS for a while
+            OpenError::Unavailable(format!("Could not resolve {}: {}", address, e))
+        }
+    })?;
+
+    let mut last_err = format!("{} did not resolve to any address", address);
+    for addr in addrs {
+        match TcpStream::connect_timeout(&addr, timeout) {
+            Ok(stream) => {
+                let _ = stream.set_nodelay(true);
+                stream
+                    .set_write_timeout(Some(timeout))
+                    .and_then(|_| stream.set_nonblocking(true))
+                    .map_err(|e| OpenError::Unavailable(e.to_string()))?;
+                return Ok(stream);
+            }
+            Err(e) => last_err = e.to_string(),
+        }
+    }
+
+    Err(OpenError::Unavailable(last_err))
+}
+
+/// Writes the whole buffer to a non-blocking stream by briefly switching it
+/// back to blocking mode (bounded by the write timeout set in [`connect`]).
+pub fn write_blocking(stream: &mut TcpStream, data: &[u8]) -> io::Result<()> {
+    stream.set_nonblocking(false)?;
+    let result = stream.write_all(data).and_then(|_| stream.flush());
+    stream.set_nonblocking(true)?;
+    result
+}
+
+impl DataSource for TcpSource {
+    fn open(&mut self) -> Result<(), OpenError> {
+        self.stream = Some(connect(&self.address, self.timeout)?);
+        Ok(())
+    }
+
+    fn is_open(&self) -> bool {
+        self.stream.is_some()
+    }
+
+    fn close(&mut self) {
+        self.stream = None;
+    }
+
+    fn read(&mut self) -> SourceEvent {
+        let Some(stream) = self.stream.as_mut() else {
+            return SourceEvent::Idle;
+        };
+
+        match stream.read(&mut self.buffer) {
+            Ok(0) => {
+                self.stream = None;
+                SourceEvent::Disconnected("Connection closed by remote host".to_string())
+            }
+            Ok(n) => SourceEvent::Bytes(self.buffer[..n].to_vec()),
+            Err(ref e)
+                if e.kind() == io::ErrorKind::WouldBlock
+                    || e.kind() == io::ErrorKind::Interrupted =>
+            {
+                SourceEvent::Idle
+            }
+            Err(e) => {
+                self.stream = None;
+                SourceEvent::Disconnected(e.to_string())
+            }
+        }
+    }
+
+    fn write(&mut self, data: &[u8]) -> io::Result<()> {
+        let stream = self
+            .stream
+            .as_mut()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotConnected, "socket is not open"))?;
+        write_blocking(stream, data)
+    }
+
+    fn is_serial(&self) -> bool {
+        true
+    }
+
+    fn describe(&self) -> String {
+        format!("{}{}", TCP_SCHEME, self.address)
+    }
+}
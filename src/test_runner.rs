@@ -0,0 +1,547 @@
+use crate::config::MergedConfig;
+use crate::serial::get_timestamp;
+use crate::source::{DataSource, OpenError, SourceEvent};
+use inline_colorization::*;
+use regex::Regex;
+use serde::Deserialize;
+use std::collections::VecDeque;
+use std::error::Error;
+use std::fs::{File, OpenOptions};
+use std::io::{BufWriter, Write};
+use std::path::Path;
+use std::process::ExitCode;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// A `comchan test` script.
+///
+/// ```toml
+/// name = "Firmware smoke test"
+/// timeout = "5s"
+///
+/// [[test]]
+/// name = "boots to shell"
+/// steps = [
+///   { toggle_dtr = "100ms" },
+///   { expect = "Booting Zephyr", within = "10s" },
+///   { send = "sensor get temp" },
+///   { expect_value = "Temperature", in = [15.0, 35.0] },
+///   { sleep = "500ms" },
+/// ]
+/// ```
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ScriptFile {
+    name: Option<String>,
+    /// Default `within` for `expect` and `expect_value`.
+    timeout: Option<String>,
+    /// How long to wait for the device to appear before giving up.
+    connect_timeout: Option<String>,
+    #[serde(rename = "test", default)]
+    tests: Vec<TestFile>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct TestFile {
+    name: String,
+    steps: Vec<toml::Table>,
+}
+
+enum Step {
+    Send(String),
+    Expect(Regex, Duration),
+    ExpectValue {
+        sensor: String,
+        min: f64,
+        max: f64,
+        within: Duration,
+    },
+    Sleep(Duration),
+    /// Raise DTR for the given time, then drop it again.
+    ToggleDtr(Duration),
+}
+
+impl Step {
+    fn describe(&self) -> String {
+        match self {
+            Step::Send(text) => format!("send {:?}", text),
+            Step::Expect(pattern, within) => format!(
+                "expect /{}/ within {}",
+                pattern,
+                humantime::format_duration(*within)
+            ),
+            Step::ExpectValue {
+                sensor, min, max, ..
+            } => format!("expect_value {} in [{}, {}]", sensor, min, max),
+            Step::Sleep(duration) => format!("sleep {}", humantime::format_duration(*duration)),
+            Step::ToggleDtr(duration) => {
+                format!("toggle_dtr {}", humantime::format_duration(*duration))
+            }
+        }
+    }
+}
+
+struct TestCase {
+    name: String,
+    steps: Vec<Step>,
+}
+
+struct Script {
+    name: String,
+    connect_timeout: Duration,
+    tests: Vec<TestCase>,
+}
+
+fn parse_duration(text: &str) -> Result<Duration, String> {
+    humantime::parse_duration(text).map_err(|e| format!("invalid duration '{}': {}", text, e))
+}
+
+fn load_script(path: &Path) -> Result<Script, Box<dyn Error>> {
+    let text = std::fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read test script '{}': {}", path.display(), e))?;
+    let file: ScriptFile = toml::from_str(&text)
+        .map_err(|e| format!("Invalid test script '{}': {}", path.display(), e))?;
+
+    let invalid = |msg: String| format!("Invalid test script '{}': {}", path.display(), msg);
+    let timeout = parse_duration(file.timeout.as_deref().unwrap_or("5s")).map_err(invalid)?;
+    let connect_timeout =
+        parse_duration(file.connect_timeout.as_deref().unwrap_or("10s")).map_err(invalid)?;
+
+    if file.tests.is_empty() {
+        return Err(invalid("no [[test]] defined".to_string()).into());
+    }
+
+    let mut tests = Vec::new();
+    for test in file.tests {
+        let mut steps = Vec::new();
+        for (index, table) in test.steps.iter().enumerate() {
+            let step = parse_step(table, timeout)
+                .map_err(|e| invalid(format!("test '{}' step {}: {}", test.name, index + 1, e)))?;
+            steps.push(step);
+        }
+        tests.push(TestCase {
+            name: test.name,
+            steps,
+        });
+    }
+
+    Ok(Script {
+        name: file.name.unwrap_or_else(|| {
+            path.file_stem()
+                .map(|s| s.to_string_lossy().to_string())
+                .unwrap_or_else(|| "comchan".to_string())
+        }),
+        connect_timeout,
+        tests,
+    })
+}
+
+/// Turns one `{ ... }` step table into a [`Step`], checking for stray keys.
+fn parse_step(table: &toml::Table, default_timeout: Duration) -> Result<Step, String> {
+    let text = |key: &str| -> Result<Option<String>, String> {
+        match table.get(key) {
+            None => Ok(None),
+            Some(toml::Value::String(s)) => Ok(Some(s.clone())),
+            Some(other) => Err(format!("`{}` must be a string, got {}", key, other)),
+        }
+    };
+    let within = || -> Result<Duration, String> {
+        match text("within")? {
+            Some(s) => parse_duration(&s),
+            None => Ok(default_timeout),
+        }
+    };
+    let only = |allowed: &[&str]| -> Result<(), String> {
+        match table.keys().find(|k| !allowed.contains(&k.as_str())) {
+            Some(key) => Err(format!("unexpected key `{}`", key)),
+            None => Ok(()),
+        }
+    };
+
+    if let Some(command) = text("send")? {
+        only(&["send"])?;
+        Ok(Step::Send(command))
+    } else if let Some(pattern) = text("expect")? {
+        only(&["expect", "within"])?;
+        let regex = Regex::new(&pattern).map_err(|e| format!("invalid regex: {}", e))?;
+        Ok(Step::Expect(regex, within()?))
+    } else if let Some(sensor) = text("expect_value")? {
+        only(&["expect_value", "in", "within"])?;
+        let range = match table.get("in") {
+            Some(toml::Value::Array(values)) if values.len() == 2 => values
+                .iter()
+                .map(|v| match v {
+                    toml::Value::Float(f) => Some(*f),
+                    toml::Value::Integer(i) => Some(*i as f64),
+                    _ => None,
+                })
+                .collect::<Option<Vec<f64>>>(),
+            _ => None,
+        }
+        .ok_or("`expect_value` needs `in = [min, max]`")?;
+        Ok(Step::ExpectValue {
+            sensor,
+            min: range[0].min(range[1]),
+            max: range[0].max(range[1]),
+            within: within()?,
+        })
+    } else if let Some(duration) = text("sleep")? {
+        only(&["sleep"])?;
+        Ok(Step::Sleep(parse_duration(&duration)?))
+    } else if let Some(duration) = text("toggle_dtr")? {
+        only(&["toggle_dtr"])?;
+        Ok(Step::ToggleDtr(parse_duration(&duration)?))
+    } else {
+        Err("expected one of send, expect, expect_value, sleep, toggle_dtr".to_string())
+    }
+}
+
+/// The device connection plus everything received that no step consumed yet.
+struct Session {
+    source: Box<dyn DataSource>,
+    rx_buf: String,
+    lines: VecDeque<String>,
+    log_writer: Option<BufWriter<File>>,
+    /// RX/TX of the current test case, reported as JUnit `system-out`.
+    transcript: String,
+}
+
+impl Session {
+    fn record(&mut self, direction: &str, line: &str) {
+        let entry = format!("{} [{}]: {}", direction, get_timestamp(), line);
+        if let Some(ref mut writer) = self.log_writer {
+            writeln!(writer, "{}", entry).ok();
+            let _ = writer.flush();
+        }
+        self.transcript.push_str(&entry);
+        self.transcript.push('\n');
+    }
+
+    fn connect(&mut self, deadline: Instant) -> Result<(), String> {
+        loop {
+            match self.source.open() {
+                Ok(()) => return Ok(()),
+                Err(OpenError::Fatal(e)) => return Err(e.to_string()),
+                Err(OpenError::Unavailable(e)) if Instant::now() >= deadline => {
+                    return Err(format!("Could not open {}: {}", self.source.describe(), e));
+                }
+                Err(OpenError::Unavailable(_)) => thread::sleep(Duration::from_millis(200)),
+            }
+        }
+    }
+
+    /// Polls the device once and queues any completed lines.
+    fn pump(&mut self) -> Result<(), String> {
+        if !self.source.is_open() {
+            // Reconnect quietly; the step deadline bounds how long we try
+            match self.source.reconnect() {
+                Ok(()) => {}
+                Err(OpenError::Fatal(e)) => return Err(e.to_string()),
+                Err(OpenError::Unavailable(_)) => thread::sleep(Duration::from_millis(50)),
+            }
+            return Ok(());
+        }
+
+        match self.source.read() {
+            SourceEvent::Bytes(raw) => {
+                self.rx_buf.push_str(&String::from_utf8_lossy(&raw));
+                while let Some(pos) = self.rx_buf.find('\n') {
+                    let line = self.rx_buf.drain(..=pos).collect::<String>();
+                    self.push_line(line);
+                }
+            }
+            SourceEvent::Lines(lines) | SourceEvent::ChannelLines(_, lines) => {
+                for line in lines {
+                    self.push_line(line);
+                }
+            }
+            SourceEvent::Idle => thread::sleep(Duration::from_millis(5)),
+            SourceEvent::Disconnected(e) => {
+                self.record("ERROR", &format!("Connection lost: {}", e));
+            }
+            SourceEvent::Eof => return Err("Input finished".to_string()),
+        }
+        Ok(())
+    }
+
+    fn push_line(&mut self, line: String) {
+        let trimmed = line.trim_end();
+        if !trimmed.is_empty() {
+            self.record("RX", trimmed);
+            self.lines.push_back(trimmed.to_string());
+        }
+    }
+
+    fn run(&mut self, step: &Step) -> Result<String, String> {
+        match step {
+            Step::Send(command) => {
+                self.source
+                    .write(format!("{}\r", command).as_bytes())
+                    .map_err(|e| format!("write failed: {}", e))?;
+                self.record("TX", command);
+                Ok(String::new())
+            }
+            Step::Expect(pattern, within) => {
+                let deadline = Instant::now() + *within;
+                loop {
+                    while let Some(line) = self.lines.pop_front() {
+                        if pattern.is_match(&line) {
+                            return Ok(line);
+                        }
+                    }
+                    // Prompts such as `uart:~$ ` never end with a newline
+                    if let Some(found) = pattern.find(&self.rx_buf) {
+                        let end = found.end();
+                        let partial = self.rx_buf.drain(..end).collect::<String>();
+                        self.record("RX", partial.trim_end());
+                        return Ok(partial);
+                    }
+                    if Instant::now() >= deadline {
+                        return Err(format!(
+                            "no line matched within {}",
+                            humantime::format_duration(*within)
+                        ));
+                    }
+                    self.pump()?;
+                }
+            }
+            Step::ExpectValue {
+                sensor,
+                min,
+                max,
+                within,
+            } => {
+                let deadline = Instant::now() + *within;
+                loop {
+                    while let Some(line) = self.lines.pop_front() {
+                        let readings = crate::parser::parse_sensor_data(&line);
+                        if let Some((_, value)) = readings.iter().find(|(name, _)| name == sensor) {
+                            return if (*min..=*max).contains(value) {
+                                Ok(format!("{} = {}", sensor, value))
+                            } else {
+                                Err(format!(
+                                    "{} = {} is outside [{}, {}]",
+                                    sensor, value, min, max
+                                ))
+                            };
+                        }
+                    }
+                    if Instant::now() >= deadline {
+                        return Err(format!(
+                            "no {} reading within {}",
+                            sensor,
+                            humantime::format_duration(*within)
+                        ));
+                    }
+                    self.pump()?;
+                }
+            }
+            Step::Sleep(duration) => {
+                // Keep reading so nothing is lost and the log stays live
+                let deadline = Instant::now() + *duration;
+                while Instant::now() < deadline {
+                    self.pump()?;
+                }
+                Ok(String::new())
+            }
+            Step::ToggleDtr(duration) => {
+                self.source
+                    .set_dtr(true)
+                    .map_err(|e| format!("DTR failed: {}", e))?;
+                thread::sleep(*duration);
+                self.source
+                    .set_dtr(false)
+                    .map_err(|e| format!("DTR failed: {}", e))?;
+                Ok(String::new())
+            }
+        }
+    }
+}
+
+struct CaseResult {
+    name: String,
+    time: Duration,
+    /// Failing step description and the reason.
+    failure: Option<(String, String)>,
+    transcript: String,
+}
+
+/// Runs `comchan test <script>` and writes a JUnit report to `junit`.
+/// Exits with failure if any test case fails.
+pub fn run_test_script(
+    config: &MergedConfig,
+    source: Box<dyn DataSource>,
+    script_path: &Path,
+    junit: &Path,
+) -> Result<ExitCode, Box<dyn Error>> {
+    let script = load_script(script_path)?;
+
+    let log_writer = match config.log_file {
+        Some(ref path) => Some(BufWriter::new(
+            OpenOptions::new().create(true).append(true).open(path)?,
+        )),
+        None => None,
+    };
+    let mut session = Session {
+        source,
+        rx_buf: String::new(),
+        lines: VecDeque::new(),
+        log_writer,
+        transcript: String::new(),
+    };
+
+    println!(
+        "{color_cyan}🧪 Running {} ({} tests) on {}{color_reset}",
+        script.name,
+        script.tests.len(),
+        session.source.describe()
+    );
+
+    let suite_started = Instant::now();
+    let connected = session.connect(Instant::now() + script.connect_timeout);
+    let mut results = Vec::new();
+
+    for test in &script.tests {
+        println!("{style_bold}▶ {}{style_reset}", test.name);
+        let started = Instant::now();
+        session.transcript.clear();
+
+        let mut failure = None;
+        if let Err(ref e) = connected {
+            failure = Some(("connect".to_string(), e.clone()));
+        } else {
+            for (index, step) in test.steps.iter().enumerate() {
+                let step_started = Instant::now();
+                match session.run(step) {
+                    Ok(detail) => println!(
+                        "  {color_green}✔{color_reset} {} {color_bright_black}{}({:.2}s){color_reset}",
+                        step.describe(),
+                        if detail.is_empty() {
+                            String::new()
+                        } else {
+                            format!("→ {} ", detail.trim_end())
+                        },
+                        step_started.elapsed().as_secs_f64()
+                    ),
+                    Err(reason) => {
+                        println!(
+                            "  {color_red}✘ {}: {}{color_reset}",
+                            step.describe(),
+                            reason
+                        );
+                        failure =
+                            Some((format!("step {}: {}", index + 1, step.describe()), reason));
+                        break;
+                    }
+                }
+            }
+        }
+
+        results.push(CaseResult {
+            name: test.name.clone(),
+            time: started.elapsed(),
+            failure,
+            transcript: std::mem::take(&mut session.transcript),
+        });
+    }
+
+    write_junit(junit, &script.name, suite_started.elapsed(), &results)
+        .map_err(|e| format!("Failed to write JUnit report '{}': {}", junit.display(), e))?;
+
+    let failed = results.iter().filter(|r| r.failure.is_some()).count();
+    if failed == 0 {
+        println!(
+            "{color_green}✅ {} passed{color_reset} (report: {})",
+            results.len(),
+            junit.display()
+        );
+        Ok(ExitCode::SUCCESS)
+    } else {
+        println!(
+            "{color_red}❌ {} of {} failed{color_reset} (report: {})",
+            failed,
+            results.len(),
+            junit.display()
+        );
+        Ok(ExitCode::FAILURE)
+    }
+}
+
+/// Escapes text for XML and drops control characters XML 1.0 cannot carry.
+fn xml_escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&apos;"),
+            '\t' | '\n' | '\r' => out.push(c),
+            c if (c as u32) < 0x20 => out.push('\u{FFFD}'),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+fn write_junit(
+    path: &Path,
+    suite: &str,
+    time: Duration,
+    results: &[CaseResult],
+) -> std::io::Result<()> {
+    let failures = results.iter().filter(|r| r.failure.is_some()).count();
+    let mut out = BufWriter::new(File::create(path)?);
+
+    writeln!(out, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+    writeln!(
+        out,
+        r#"<testsuites name="{}" tests="{}" failures="{}" time="{:.3}">"#,
+        xml_escape(suite),
+        results.len(),
+        failures,
+        time.as_secs_f64()
+    )?;
+    writeln!(
+        out,
+        r#"  <testsuite name="{}" tests="{}" failures="{}" errors="0" skipped="0" time="{:.3}" timestamp="{}">"#,
+        xml_escape(suite),
+        results.len(),
+        failures,
+        time.as_secs_f64(),
+        chrono::Local::now().format("%Y-%m-%dT%H:%M:%S")
+    )?;
+
+    for result in results {
+        writeln!(
+            out,
+            r#"    <testcase name="{}" classname="{}" time="{:.3}">"#,
+            xml_escape(&result.name),
+            xml_escape(suite),
+            result.time.as_secs_f64()
+        )?;
+        if let Some((step, reason)) = &result.failure {
+            writeln!(
+                out,
+                r#"      <failure message="{}">{}: {}</failure>"#,
+                xml_escape(reason),
+                xml_escape(step),
+                xml_escape(reason)
+            )?;
+        }
+        if !result.transcript.is_empty() {
+            writeln!(
+                out,
+                "      <system-out>{}</system-out>",
+                xml_escape(&result.transcript)
+            )?;
+        }
+        writeln!(out, "    </testcase>")?;
+    }
+
+    writeln!(out, "  </testsuite>")?;
+    writeln!(out, "</testsuites>")?;
+    out.flush()
+}
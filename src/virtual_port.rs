@@ -0,0 +1,332 @@
+use crate::source::{DataSource, SimProfile, SimulatedSource, SourceEvent};
+use nix::fcntl::{FcntlArg, OFlag, fcntl};
+use nix::pty::openpty;
+use nix::sys::termios::{SetArg, cfmakeraw, tcgetattr, tcsetattr};
+use std::fs::File;
+use std::io::{self, Read, Write};
+use std::os::fd::OwnedFd;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+/// One directive of a `--virtual-script` file.
+enum Step {
+    /// Raw bytes written to the host as-is.
+    Write(Vec<u8>),
+    Sleep(Duration),
+    /// Echo host input back, like an interactive shell does.
+    Echo(bool),
+    /// Drop the link for a while, then come back on a fresh PTY.
+    Hangup(Duration),
+    /// Jump back to the first step.
+    Repeat,
+}
+
+/// What the virtual device sends to the host.
+enum Generator {
+    /// The same streams `--simulate` produces, but delivered over a real tty.
+    Profile(SimulatedSource),
+    Script {
+        steps: Vec<Step>,
+        next: usize,
+    },
+}
+
+/// A pseudo-terminal pair that behaves like a board plugged into the machine.
+///
+/// ComChan opens the slave side through the normal serial path, while a
+/// background thread plays the device on the master side. The slave is
+/// reached through a stable symlink so that a scripted `hangup` can swap in
+/// a new PTY and exercise the reconnect logic.
+pub struct VirtualPort {
+    link: PathBuf,
+    running: Arc<AtomicBool>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl VirtualPort {
+    /// Creates the device. `script` is a `--virtual-script` file; without one
+    /// the device streams the simulator's `profile`.
+    pub fn create(
+        script: Option<&str>,
+        profile: SimProfile,
+        echo: bool,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        let generator = match script {
+            Some(path) => {
+                let text = std::fs::read_to_string(path)
+                    .map_err(|e| format!("Failed to read virtual script '{}': {}", path, e))?;
+                Generator::Script {
+                    steps: parse_script(&text).map_err(|e| format!("{}:{}", path, e))?,
+                    next: 0,
+                }
+            }
+            None => {
+                let mut sim = SimulatedSource::new(profile);
+                let _ = sim.open();
+                Generator::Profile(sim)
+            }
+        };
+
+        let link = std::env::temp_dir().join(format!("comchan-virtual-{}", std::process::id()));
+        let pty = Pty::open()?;
+        point_link(&link, &pty.slave_path)?;
+
+        let running = Arc::new(AtomicBool::new(true));
+        let device = Device {
+            pty: Some(pty),
+            link: link.clone(),
+            generator,
+            echo,
+            resume_at: Instant::now(),
+            running: running.clone(),
+        };
+        let handle = thread::spawn(move || device.run());
+
+        Ok(Self {
+            link,
+            running,
+            handle: Some(handle),
+        })
+    }
+
+    /// Path to hand to the serial code (`-p`); survives hangups.
+    pub fn path(&self) -> String {
+        self.link.display().to_string()
+    }
+}
+
+impl Drop for VirtualPort {
+    fn drop(&mut self) {
+        self.running.store(false, Ordering::Relaxed);
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+        let _ = std::fs::remove_file(&self.link);
+    }
+}
+
+struct Pty {
+    master: File,
+    // Held open so writes from the device are buffered until the host connects
+    _slave: OwnedFd,
+    slave_path: PathBuf,
+}
+
+impl Pty {
+    fn open() -> io::Result<Self> {
+        let pair = openpty(None, None).map_err(io::Error::from)?;
+
+        // Without raw mode the line discipline would echo and translate the
+        // device output before ComChan ever configures the port.
+        let mut termios = tcgetattr(&pair.slave).map_err(io::Error::from)?;
+        cfmakeraw(&mut termios);
+        tcsetattr(&pair.slave, SetArg::TCSANOW, &termios).map_err(io::Error::from)?;
+
+        fcntl(&pair.master, FcntlArg::F_SETFL(OFlag::O_NONBLOCK)).map_err(io::Error::from)?;
+        let slave_path = nix::unistd::ttyname(&pair.slave).map_err(io::Error::from)?;
+
+        Ok(Self {
+            master: File::from(pair.master),
+            _slave: pair.slave,
+            slave_path,
+        })
+    }
+}
+
+/// Atomically re-points `link` at `target`.
+fn point_link(link: &Path, target: &Path) -> io::Result<()> {
+    let staging = link.with_extension("new");
+    let _ = std::fs::remove_file(&staging);
+    std::os::unix::fs::symlink(target, &staging)?;
+    std::fs::rename(&staging, link)
+}
+
+struct Device {
+    pty: Option<Pty>,
+    link: PathBuf,
+    generator: Generator,
+    echo: bool,
+    resume_at: Instant,
+    running: Arc<AtomicBool>,
+}
+
+impl Device {
+    fn run(mut self) {
+        let mut buffer = [0u8; 1024];
+
+        while self.running.load(Ordering::Relaxed) {
+            let Some(pty) = self.pty.as_mut() else {
+                // Unplugged by a hangup step
+                if Instant::now() >= self.resume_at
+                    && let Ok(pty) = Pty::open()
+                {
+                    let _ = point_link(&self.link, &pty.slave_path);
+                    self.pty = Some(pty);
+                }
+                thread::sleep(Duration::from_millis(10));
+                continue;
+            };
+
+            // EIO on a master means no fd has the slave open, which includes a
+            // host that closed the port. `_slave` keeps one open, so a host can
+            // close and reopen freely; nothing to read is just `WouldBlock`.
+            if let Ok(n) = pty.master.read(&mut buffer)
+                && n > 0
+                && self.echo
+            {
+                let _ = pty.master.write_all(&buffer[..n]);
+            }
+
+            self.step();
+            thread::sleep(Duration::from_millis(5));
+        }
+    }
+
+    /// Advances the generator as far as the clock allows.
+    fn step(&mut self) {
+        match &mut self.generator {
+            Generator::Profile(sim) => {
+                if let SourceEvent::Bytes(data) = sim.read()
+                    && let Some(pty) = self.pty.as_mut()
+                {
+                    let _ = pty.master.write_all(&data);
+                }
+            }
+            Generator::Script { steps, next } => {
+                while Instant::now() >= self.resume_at && self.pty.is_some() {
+                    let Some(step) = steps.get(*next) else {
+                        return;
+                    };
+                    *next += 1;
+
+                    match step {
+                        Step::Write(data) => {
+                            if let Some(pty) = self.pty.as_mut() {
+                                let _ = pty.master.write_all(data);
+                            }
+                        }
+                        Step::Sleep(duration) => self.resume_at = Instant::now() + *duration,
+                        Step::Echo(on) => self.echo = *on,
+                        Step::Hangup(duration) => {
+                            self.pty = None;
+                            self.resume_at = Instant::now() + *duration;
+                        }
+                        Step::Repeat => {
+                            *next = 0;
+                            // A script without sleeps would otherwise spin forever
+                            if !steps.iter().any(|s| matches!(s, Step::Sleep(_))) {
+                                self.resume_at = Instant::now() + Duration::from_millis(100);
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Parses a virtual device script. One directive per line, `#` starts a comment:
+///
+/// ```text
+/// send Temperature: 21.5   # text followed by \r\n
+/// write AT\x0d             # text without a line ending
+/// hex DE AD BE EF
+/// sleep 500ms
+/// echo on
+/// hangup 2s
+/// repeat
+/// ```
+fn parse_script(text: &str) -> Result<Vec<Step>, String> {
+    let mut steps = Vec::new();
+
+    for (index, raw_line) in text.lines().enumerate() {
+        let line = strip_comment(raw_line).trim();
+        if line.is_empty() {
+            continue;
+        }
+
+        let (command, arg) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+        let arg = arg.trim();
+        let err = |msg: String| format!("{}: {}", index + 1, msg);
+
+        let step = match command {
+            "send" => {
+                let mut data = unescape(arg).map_err(err)?;
+                data.extend_from_slice(b"\r\n");
+                Step::Write(data)
+            }
+            "write" => Step::Write(unescape(arg).map_err(err)?),
+            "hex" => Step::Write(
+                arg.split_whitespace()
+                    .map(|b| u8::from_str_radix(b, 16))
+                    .collect::<Result<_, _>>()
+                    .map_err(|e| err(format!("invalid hex byte: {}", e)))?,
+            ),
+            "sleep" => Step::Sleep(parse_duration(arg).map_err(err)?),
+            "hangup" => Step::Hangup(parse_duration(arg).map_err(err)?),
+            "echo" => match arg {
+                "on" => Step::Echo(true),
+                "off" => Step::Echo(false),
+                _ => return Err(err(format!("expected 'on' or 'off', got '{}'", arg))),
+            },
+            "repeat" => Step::Repeat,
+            _ => return Err(err(format!("unknown directive '{}'", command))),
+        };
+        steps.push(step);
+    }
+
+    Ok(steps)
+}
+
+/// Strips a trailing `# comment` unless the `#` is escaped as `\#`.
+fn strip_comment(line: &str) -> &str {
+    let mut escaped = false;
+    for (i, c) in line.char_indices() {
+        match c {
+            '\\' => escaped = !escaped,
+            '#' if !escaped => return &line[..i],
+            _ => escaped = false,
+        }
+    }
+    line
+}
+
+fn parse_duration(arg: &str) -> Result<Duration, String> {
+    humantime::parse_duration(arg).map_err(|e| format!("invalid duration '{}': {}", arg, e))
+}
+
+/// Expands `\r`, `\n`, `\t`, `\0`, `\\`, `\#` and `\xHH` escapes.
+fn unescape(arg: &str) -> Result<Vec<u8>, String> {
+    let mut out = Vec::with_capacity(arg.len());
+    let mut chars = arg.chars();
+
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            let mut utf8 = [0u8; 4];
+            out.extend_from_slice(c.encode_utf8(&mut utf8).as_bytes());
+            continue;
+        }
+
+        match chars.next() {
+            Some('r') => out.push(b'\r'),
+            Some('n') => out.push(b'\n'),
+            Some('t') => out.push(b'\t'),
+            Some('0') => out.push(0),
+            Some('\\') => out.push(b'\\'),
+            Some('#') => out.push(b'#'),
+            Some('x') => {
+                let hex: String = chars.by_ref().take(2).collect();
+                let byte = u8::from_str_radix(&hex, 16)
+                    .map_err(|_| format!("invalid escape '\\x{}'", hex))?;
+                out.push(byte);
+            }
+            Some(other) => return Err(format!("unknown escape '\\{}'", other)),
+            None => return Err("trailing backslash".to_string()),
+        }
+    }
+
+    Ok(out)
+}